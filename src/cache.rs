@@ -0,0 +1,38 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Part of every entry name, bumped whenever the format of answers changes so that
+// entries in an older format are never read
+const FORMAT_VERSION: u32 = 3;
+
+/// An on-disk cache of the answers of searches, keyed by formula fingerprints, before
+/// any output options such as --partial or --soft change them
+pub struct AnswerCache {
+    dir: PathBuf,
+}
+
+impl AnswerCache {
+    /// Opens the cache in the given directory, creating it if necessary
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, String> {
+        fs::create_dir_all(&dir).map_err(|_| "Failed to create cache directory")?;
+        Ok(AnswerCache { dir: dir.as_ref().to_owned() })
+    }
+
+    /// Returns the answer stored for the fingerprint, if any
+    pub fn lookup(&self, fingerprint: u64) -> Option<String> {
+        fs::read_to_string(self.entry(fingerprint)).ok()
+    }
+
+    /// Stores the answer for the fingerprint, replacing any previous answer
+    pub fn store(&self, fingerprint: u64, answer: &str) -> Result<(), String> {
+        // Write to a temporary file first so concurrent runs never read half an entry
+        let tmp = self.dir.join(format!("{:016x}-v{}.tmp", fingerprint, FORMAT_VERSION));
+        fs::write(&tmp, answer)
+            .and_then(|_| fs::rename(&tmp, self.entry(fingerprint)))
+            .map_err(|_| "Failed to write cache entry".to_owned())
+    }
+
+    fn entry(&self, fingerprint: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}-v{}.txt", fingerprint, FORMAT_VERSION))
+    }
+}
@@ -0,0 +1,137 @@
+use core::time::Duration;
+use crate::heuristic::{Heuristic, Phase};
+use crate::solver::Engine;
+
+// Formulas with fewer variables than this skip subsumption and binary simplification,
+// which includes equivalence substitution, since setting them up costs more than they
+// save on tiny inputs. The passes which are off by default still run when enabled, as
+// asking for them says they are worth it
+const FAST_PATH_VARS: usize = 50;
+
+/// The tunable parameters of the solver. Start from the defaults and change them
+/// with the builder methods, e.g. `SolverConfig::default().heuristic(Heuristic::Dlis)`,
+/// and apply them with `Solver::with_config`, or with `Formula::configure` and
+/// `Formula::preprocess`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SolverConfig {
+    /// The branching heuristic used by DPLL
+    pub heuristic: Heuristic,
+    /// The polarity variables are tried in first
+    pub phase: Phase,
+    /// The search algorithm, along with its seed for local search
+    pub engine: Engine,
+    /// Whether to remove subsumed clauses and strengthen clauses by self-subsuming resolution
+    pub subsumption: bool,
+    /// Whether to simplify the binary implication graph, see `Formula::simplify_binary`
+    pub binary: bool,
+    /// Whether to add symmetry-breaking clauses, see `Formula::break_symmetries`
+    pub break_symmetries: bool,
+    /// Clauses longer than this are split into ternary clauses, if set
+    pub ternary_threshold: Option<usize>,
+    /// Formulas with fewer variables than this skip subsumption and binary simplification.
+    /// Symmetry breaking and clause splitting still run on them if enabled
+    pub fast_path_vars: usize,
+    /// The seed for perturbing clause order and tie-breaking, see
+    /// `Formula::set_chaos_seed`. The seed of WalkSAT is part of the engine
+    pub chaos_seed: Option<u64>,
+    /// Conflicts after which the search gives up, see `Formula::set_conflict_limit`
+    pub conflict_limit: Option<u64>,
+    /// Propagations after which the search gives up, see `Formula::set_propagation_limit`
+    pub propagation_limit: Option<u64>,
+    /// Time after which the search gives up, see `Formula::set_time_limit`
+    pub time_limit: Option<Duration>,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            heuristic: Heuristic::Ordered,
+            phase: Phase::Positive,
+            engine: Engine::Dpll,
+            subsumption: true,
+            binary: true,
+            break_symmetries: false,
+            ternary_threshold: None,
+            fast_path_vars: FAST_PATH_VARS,
+            chaos_seed: None,
+            conflict_limit: None,
+            propagation_limit: None,
+            time_limit: None,
+        }
+    }
+}
+
+impl SolverConfig {
+    /// Sets the branching heuristic
+    pub fn heuristic(mut self, heuristic: Heuristic) -> Self {
+        self.heuristic = heuristic;
+        self
+    }
+
+    /// Sets the polarity variables are tried in first
+    pub fn phase(mut self, phase: Phase) -> Self {
+        self.phase = phase;
+        self
+    }
+
+    /// Sets the search algorithm
+    pub fn engine(mut self, engine: Engine) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Sets whether to run subsumption and self-subsuming resolution
+    pub fn subsumption(mut self, subsumption: bool) -> Self {
+        self.subsumption = subsumption;
+        self
+    }
+
+    /// Sets whether to simplify the binary implication graph
+    pub fn binary(mut self, binary: bool) -> Self {
+        self.binary = binary;
+        self
+    }
+
+    /// Sets whether to add symmetry-breaking clauses
+    pub fn break_symmetries(mut self, break_symmetries: bool) -> Self {
+        self.break_symmetries = break_symmetries;
+        self
+    }
+
+    /// Sets the length above which clauses are split, or None to keep them
+    pub fn ternary_threshold(mut self, threshold: Option<usize>) -> Self {
+        self.ternary_threshold = threshold;
+        self
+    }
+
+    /// Sets the number of variables below which the costlier simplifications are
+    /// skipped, where 0 never skips them
+    pub fn fast_path_vars(mut self, fast_path_vars: usize) -> Self {
+        self.fast_path_vars = fast_path_vars;
+        self
+    }
+
+    /// Sets the chaos seed, or None to turn chaos mode off
+    pub fn chaos_seed(mut self, seed: Option<u64>) -> Self {
+        self.chaos_seed = seed;
+        self
+    }
+
+    /// Sets the conflict limit, or None for no limit
+    pub fn conflict_limit(mut self, limit: Option<u64>) -> Self {
+        self.conflict_limit = limit;
+        self
+    }
+
+    /// Sets the propagation limit, or None for no limit
+    pub fn propagation_limit(mut self, limit: Option<u64>) -> Self {
+        self.propagation_limit = limit;
+        self
+    }
+
+    /// Sets the time limit, or None for no limit
+    pub fn time_limit(mut self, limit: Option<Duration>) -> Self {
+        self.time_limit = limit;
+        self
+    }
+}
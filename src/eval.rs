@@ -0,0 +1,69 @@
+// A deliberately naive CNF evaluator which shares no data structures with the
+// solver, so that it can serve as a trusted oracle for the solver's answers
+
+/// Returns the index of the first clause not satisfied by the assignment, if any.
+/// Each clause is a list of DIMACS literals, e.g. 3 or -42, and `values[i]` is the
+/// value of DIMACS variable `i + 1`. Variables beyond `values` count as false
+pub fn first_falsified(clauses: &[Vec<isize>], values: &[bool]) -> Option<usize> {
+    clauses.iter().position(|clause| !clause.iter().any(|&lit| {
+        let value = values.get(lit.unsigned_abs() - 1).copied().unwrap_or(false);
+        value == (lit > 0)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use simple_sat_solver::generate;
+    use super::first_falsified;
+
+    #[test]
+    fn satisfying_values_falsify_nothing() {
+        let clauses = vec![vec![1, -2], vec![2, 3], vec![-1, -3, 2]];
+        assert_eq!(first_falsified(&clauses, &[true, true, false]), None);
+        assert_eq!(first_falsified(&[], &[]), None);
+    }
+
+    #[test]
+    fn finds_the_first_falsified_clause() {
+        let clauses = vec![vec![1, 2], vec![-1], vec![-2], vec![1]];
+        assert_eq!(first_falsified(&clauses, &[true, false]), Some(1));
+        assert_eq!(first_falsified(&clauses, &[false, true]), Some(2));
+        assert_eq!(first_falsified(&clauses, &[false, false]), Some(0));
+    }
+
+    #[test]
+    fn variables_beyond_the_values_are_false() {
+        let clauses = vec![vec![-3], vec![1, 3]];
+        assert_eq!(first_falsified(&clauses, &[false]), Some(1));
+        assert_eq!(first_falsified(&clauses, &[true]), None);
+        assert_eq!(first_falsified(&[vec![5]], &[]), Some(0));
+    }
+
+    #[test]
+    fn the_empty_clause_is_falsified() {
+        let clauses = vec![vec![1], vec![]];
+        assert_eq!(first_falsified(&clauses, &[true]), Some(1));
+        assert_eq!(first_falsified(&[vec![]], &[]), Some(0));
+    }
+
+    #[test]
+    fn agrees_with_enumeration_on_random_formulas() {
+        for seed in 0..40 {
+            let num_vars = 6;
+            let formula = generate::random_k_sat(num_vars, 1 + seed as usize % 3, 3.0, seed);
+            let clauses: Vec<Vec<isize>> = formula.clauses()
+                .map(|c| c.iter().map(|l| l.to_var()).collect())
+                .collect();
+            let mut models = 0;
+            for bits in 0..1u32 << num_vars {
+                let values: Vec<_> = (0..num_vars).map(|id| bits >> id & 1 == 1).collect();
+                let satisfied = |clause: &Vec<isize>| clause.iter()
+                    .any(|&lit| values[lit.unsigned_abs() - 1] == (lit > 0));
+                let expected = clauses.iter().position(|clause| !satisfied(clause));
+                assert_eq!(first_falsified(&clauses, &values), expected);
+                models += expected.is_none() as u128;
+            }
+            assert_eq!(formula.count_models(), Some(models));
+        }
+    }
+}
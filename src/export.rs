@@ -0,0 +1,98 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+use crate::solver::{Formula, Literal, Trace};
+
+/// Writes the formula in DIMACS CNF format
+pub fn write_dimacs(formula: &Formula, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "p cnf {} {}", formula.num_vars(), formula.clauses().count())?;
+    for clause in formula.clauses() {
+        for lit in clause {
+            write!(out, "{} ", lit.to_var())?;
+        }
+        writeln!(out, "0")?;
+    }
+    Ok(())
+}
+
+/// Writes the formula along with the cubes in iCNF format, as used by cube-and-conquer
+pub fn write_icnf(formula: &Formula, cubes: &[Vec<Literal>], out: &mut impl Write)
+    -> io::Result<()> {
+    writeln!(out, "p inccnf")?;
+    for clause in formula.clauses() {
+        for lit in clause {
+            write!(out, "{} ", lit.to_var())?;
+        }
+        writeln!(out, "0")?;
+    }
+    for cube in cubes {
+        write!(out, "a ")?;
+        for lit in cube {
+            write!(out, "{} ", lit.to_var())?;
+        }
+        writeln!(out, "0")?;
+    }
+    Ok(())
+}
+
+/// Writes the variable incidence graph of the formula in Graphviz DOT format.
+/// There is a node per variable, named by its DIMACS number, and an edge between
+/// every two variables sharing a clause, weighted by the number of shared clauses
+pub fn write_vig(formula: &Formula, out: &mut impl Write) -> io::Result<()> {
+    let mut edges = BTreeMap::new();
+    for clause in formula.clauses() {
+        let mut ids: Vec<_> = clause.iter().map(|l| l.id()).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        for (i, &a) in ids.iter().enumerate() {
+            for &b in &ids[i + 1..] {
+                *edges.entry((a, b)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    writeln!(out, "graph vig {{")?;
+    for id in 0..formula.num_vars() {
+        writeln!(out, "    {};", id + 1)?;
+    }
+    for ((a, b), weight) in edges {
+        writeln!(out, "    {} -- {} [weight={}];", a + 1, b + 1, weight)?;
+    }
+    writeln!(out, "}}")
+}
+
+/// Writes the implications given by the binary clauses of the formula as an edge list.
+/// Each line `a b` means that DIMACS literal `a` implies DIMACS literal `b`, so the
+/// clause (a, b) gives the lines `-a b` and `-b a`
+pub fn write_binary_implications(formula: &Formula, out: &mut impl Write) -> io::Result<()> {
+    let mut edges = BTreeSet::new();
+    for clause in formula.clauses() {
+        if let [a, b] = *clause {
+            if a != b && a != !b {
+                edges.insert(((!a).to_var(), b.to_var()));
+                edges.insert(((!b).to_var(), a.to_var()));
+            }
+        }
+    }
+
+    for (from, to) in edges {
+        writeln!(out, "{} {}", from, to)?;
+    }
+    Ok(())
+}
+
+/// Writes the resolution derivation in TraceCheck format, numbering the clauses from 1
+/// in order. Each line holds a clause followed by the clauses it was resolved from
+pub fn write_tracecheck(trace: &Trace, out: &mut impl Write) -> io::Result<()> {
+    for (i, (clause, antecedents)) in trace.steps().enumerate() {
+        write!(out, "{} ", i + 1)?;
+        for lit in clause {
+            write!(out, "{} ", lit.to_var())?;
+        }
+        write!(out, "0 ")?;
+        for antecedent in antecedents {
+            write!(out, "{} ", antecedent + 1)?;
+        }
+        writeln!(out, "0")?;
+    }
+    Ok(())
+}
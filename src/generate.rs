@@ -0,0 +1,89 @@
+//! Generators for classic families of benchmark formulas, for testing and fuzzing the
+//! solver. Randomized families are reproducible from their seed
+
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::rng::Rng;
+use crate::solver::{Formula, Literal};
+
+/// Uniform random k-SAT over `num_vars` variables with `ratio * num_vars` clauses, rounded
+/// to the nearest whole number, each over k distinct variables with random polarities.
+/// Around the ratio 4.26 random 3-SAT is the hardest, with about half of the formulas
+/// satisfiable. Panics if k exceeds the number of variables
+pub fn random_k_sat(num_vars: usize, k: usize, ratio: f64, seed: u64) -> Formula {
+    assert!(k <= num_vars, "Clauses of {} distinct variables need more variables", k);
+    let mut rng = Rng::new(seed);
+    let mut formula = Formula::new();
+    for _ in 0..num_vars {
+        formula.new_var();
+    }
+    let num_clauses = (ratio * num_vars as f64 + 0.5) as usize;
+    for _ in 0..num_clauses {
+        let mut clause: Vec<Literal> = vec![];
+        while clause.len() < k {
+            let id = rng.below(num_vars);
+            if clause.iter().all(|lit| lit.id() != id) {
+                clause.push(Literal::new(id, rng.chance(0.5)));
+            }
+        }
+        formula.add_clause(clause);
+    }
+    formula
+}
+
+/// The pigeonhole principle for `holes + 1` pigeons and the given number of holes: every
+/// pigeon sits in some hole and no two share one. Unsatisfiable, and exponentially hard
+/// for resolution and thus for DPLL. Variable `p * holes + h + 1` places pigeon p in hole h
+pub fn pigeonhole(holes: usize) -> Formula {
+    let mut formula = Formula::new();
+    let pigeons = holes + 1;
+    let sits: Vec<Vec<_>> = (0..pigeons)
+        .map(|_| (0..holes).map(|_| formula.new_var().positive()).collect())
+        .collect();
+    for hole_of in &sits {
+        formula.add_clause(hole_of.iter().copied());
+    }
+    for (p, hole_of) in sits.iter().enumerate() {
+        for other in &sits[p + 1..] {
+            for (&a, &b) in hole_of.iter().zip(other) {
+                formula.add_clause([!a, !b]);
+            }
+        }
+    }
+    formula
+}
+
+/// States that the XOR of the `num_vars` variables is true twice, each time through a
+/// chain of auxiliary variables for the running XOR, the second time over the variables in
+/// a random order. If not satisfiable, the second chain states that the XOR is false
+/// instead, which only reasoning about the whole chains can refute. Panics if there are no
+/// variables
+pub fn parity(num_vars: usize, seed: u64, satisfiable: bool) -> Formula {
+    assert!(num_vars > 0, "A parity formula needs variables");
+    let mut rng = Rng::new(seed);
+    let mut formula = Formula::new();
+    let vars: Vec<_> = (0..num_vars).map(|_| formula.new_var().positive()).collect();
+    let mut shuffled = vars.clone();
+    rng.shuffle(&mut shuffled);
+
+    let first = xor_chain(&mut formula, &vars);
+    let second = xor_chain(&mut formula, &shuffled);
+    formula.add_clause([first]);
+    formula.add_clause([if satisfiable { second } else { !second }]);
+    formula
+}
+
+// Returns a literal defined to be the XOR of the given literals, through an auxiliary
+// variable for each prefix past the first literal
+fn xor_chain(formula: &mut Formula, literals: &[Literal]) -> Literal {
+    let mut sum = literals[0];
+    for &lit in &literals[1..] {
+        let next = formula.new_var().positive();
+        formula.add_clause([!sum, !lit, !next]);
+        formula.add_clause([sum, lit, !next]);
+        formula.add_clause([sum, !lit, next]);
+        formula.add_clause([!sum, lit, next]);
+        sum = next;
+    }
+    sum
+}
@@ -0,0 +1,154 @@
+use core::str::FromStr;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::solver::{Formula, Literal};
+
+/// Hooks through which the search consults a branching heuristic.
+/// Implement this to try out a new heuristic without touching the search itself
+pub trait DecisionHeuristic {
+    /// Called once before the search starts
+    fn init(&mut self, _formula: &Formula) {}
+
+    /// Called whenever a literal is assigned, either by a decision or by the search
+    /// flipping a decision after backtracking
+    fn on_assign(&mut self, _lit: Literal) {}
+
+    /// Called whenever a literal is unassigned during backtracking
+    fn on_un_assign(&mut self, _lit: Literal) {}
+
+    /// Called with the falsified clause whenever the search hits a conflict
+    fn on_conflict(&mut self, _clause: &[Literal]) {}
+
+    /// Returns the next literal to branch on, which must be unassigned.
+    /// Only called when the formula has an unassigned variable
+    fn pick_branch_literal(&mut self, formula: &Formula) -> Literal;
+}
+
+/// The built-in branching heuristics
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Heuristic {
+    /// See `Ordered`
+    Ordered,
+    /// See `JeroslowWang`
+    JeroslowWang,
+    /// See `Dlis`
+    Dlis,
+}
+
+impl Heuristic {
+    /// Creates a fresh instance of the heuristic
+    pub fn build(self) -> Box<dyn DecisionHeuristic> {
+        match self {
+            Heuristic::Ordered => Box::new(Ordered::default()),
+            Heuristic::JeroslowWang => Box::new(JeroslowWang::default()),
+            Heuristic::Dlis => Box::new(Dlis),
+        }
+    }
+}
+
+impl FromStr for Heuristic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ordered" => Ok(Heuristic::Ordered),
+            "jw" => Ok(Heuristic::JeroslowWang),
+            "dlis" => Ok(Heuristic::Dlis),
+            _ => Err(format!("Unknown heuristic '{}'", s)),
+        }
+    }
+}
+
+/// The polarity a variable is tried in first when the heuristic only picks a variable
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Phase {
+    Positive,
+    Negative,
+    /// The polarity the variable occurs in most often in the input, positive on ties
+    Occurrence,
+}
+
+impl FromStr for Phase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "positive" => Ok(Phase::Positive),
+            "negative" => Ok(Phase::Negative),
+            "occ" => Ok(Phase::Occurrence),
+            _ => Err(format!("Unknown phase '{}'", s)),
+        }
+    }
+}
+
+/// Branches on the unassigned variable with the lowest id, trying it in the
+/// formula's preferred phase first
+#[derive(Default)]
+pub struct Ordered {
+    // Every variable below this id is assigned
+    next_id: usize,
+}
+
+impl DecisionHeuristic for Ordered {
+    fn init(&mut self, _formula: &Formula) {
+        self.next_id = 0;
+    }
+
+    fn on_assign(&mut self, lit: Literal) {
+        self.next_id = lit.id() + 1;
+    }
+
+    fn on_un_assign(&mut self, lit: Literal) {
+        self.next_id = lit.id();
+    }
+
+    fn pick_branch_literal(&mut self, formula: &Formula) -> Literal {
+        let id = (self.next_id..)
+            .find(|&i| formula.value(Literal::new(i, false)).is_none()).unwrap();
+        formula.preferred_literal(id)
+    }
+}
+
+/// Branches on the literal maximizing the sum of 2^-|C| over the clauses C containing it
+#[derive(Default)]
+pub struct JeroslowWang {
+    // Weight of each literal, indexed by Literal::index
+    weights: Vec<f64>,
+}
+
+impl DecisionHeuristic for JeroslowWang {
+    fn init(&mut self, formula: &Formula) {
+        self.weights = vec![0.0; 2 * formula.num_vars()];
+        for clause in formula.clauses() {
+            // Halved once per literal, since powi needs the standard library
+            let weight = clause.iter().fold(1.0, |weight, _| weight / 2.0);
+            for lit in clause {
+                self.weights[lit.index()] += weight;
+            }
+        }
+    }
+
+    fn pick_branch_literal(&mut self, formula: &Formula) -> Literal {
+        formula.best_un_assigned(|lit| self.weights[lit.index()])
+    }
+}
+
+/// Branches on the literal occurring in the most currently unsatisfied clauses
+pub struct Dlis;
+
+impl DecisionHeuristic for Dlis {
+    fn pick_branch_literal(&mut self, formula: &Formula) -> Literal {
+        let mut counts = vec![0; 2 * formula.num_vars()];
+        let unsatisfied = formula.clauses()
+            .filter(|c| !c.iter().any(|&l| formula.value(l) == Some(true)));
+        for clause in unsatisfied {
+            for lit in clause.iter().filter(|&&l| formula.value(l).is_none()) {
+                counts[lit.index()] += 1;
+            }
+        }
+        formula.best_un_assigned(|lit| counts[lit.index()] as f64)
+    }
+}
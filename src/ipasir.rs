@@ -0,0 +1,168 @@
+// The IPASIR interface for incremental SAT solvers, see
+// https://github.com/biotomas/ipasir. Solvers are handed out as opaque pointers
+// to an IpasirSolver, and literals are DIMACS literals. Build the C library with
+// `cargo rustc --release --lib --features ipasir --crate-type staticlib` (or cdylib)
+
+use std::ffi::c_void;
+use std::os::raw::{c_char, c_int};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::heuristic::Heuristic;
+use crate::solver::{Formula, Literal, ProgressCallback, SolveResult, Solver};
+
+// Decisions between two calls of the terminate callback
+const TERMINATE_INTERVAL: u64 = 1 << 10;
+
+struct IpasirSolver {
+    solver: Solver,
+    // The clause being added, up to the terminating 0
+    clause: Vec<Literal>,
+    assumptions: Vec<Literal>,
+    // Set by the terminate callback to stop the search
+    interrupt: Arc<AtomicBool>,
+}
+
+impl IpasirSolver {
+    // Adds variables until the literal's variable exists
+    fn declare(&mut self, lit: Literal) {
+        while self.solver.formula().num_vars() <= lit.id() {
+            self.solver.new_var();
+        }
+    }
+}
+
+// The callback and the pointer passed to it, which IPASIR requires to be usable
+// from wherever the search runs
+struct Terminate {
+    data: *mut c_void,
+    callback: extern "C" fn(*mut c_void) -> c_int,
+}
+
+unsafe impl Send for Terminate {}
+unsafe impl Sync for Terminate {}
+
+// Casts the pointer handed out by ipasir_init back to the solver
+unsafe fn solver<'a>(solver: *mut c_void) -> &'a mut IpasirSolver {
+    &mut *(solver as *mut IpasirSolver)
+}
+
+#[no_mangle]
+pub extern "C" fn ipasir_signature() -> *const c_char {
+    concat!("simple-sat-solver-", env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+#[no_mangle]
+pub extern "C" fn ipasir_init() -> *mut c_void {
+    let interrupt = Arc::new(AtomicBool::new(false));
+    let mut solver = Solver::new(Formula::new(), Heuristic::Ordered.build());
+    solver.set_interrupt(Some(Arc::clone(&interrupt)));
+    let solver = IpasirSolver { solver, clause: vec![], assumptions: vec![], interrupt };
+    Box::into_raw(Box::new(solver)) as *mut c_void
+}
+
+/// # Safety
+/// The solver must come from `ipasir_init` and not have been released
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_release(solver: *mut c_void) {
+    drop(Box::from_raw(solver as *mut IpasirSolver));
+}
+
+/// # Safety
+/// The solver must come from `ipasir_init` and not have been released
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_add(solver: *mut c_void, lit_or_zero: i32) {
+    let solver = self::solver(solver);
+    if lit_or_zero == 0 {
+        let clause = std::mem::take(&mut solver.clause);
+        solver.solver.add_clause(clause);
+    } else {
+        let lit = Literal::from(lit_or_zero);
+        solver.declare(lit);
+        solver.clause.push(lit);
+    }
+}
+
+/// # Safety
+/// The solver must come from `ipasir_init` and not have been released
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_assume(solver: *mut c_void, lit: i32) {
+    let solver = self::solver(solver);
+    let lit = Literal::from(lit);
+    solver.declare(lit);
+    solver.assumptions.push(lit);
+}
+
+/// Returns 10 if satisfiable, 20 if unsatisfiable and 0 if interrupted
+///
+/// # Safety
+/// The solver must come from `ipasir_init` and not have been released
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_solve(solver: *mut c_void) -> c_int {
+    let solver = self::solver(solver);
+    solver.interrupt.store(false, Ordering::Relaxed);
+    let assumptions = std::mem::take(&mut solver.assumptions);
+    match solver.solver.solve_under(&assumptions) {
+        SolveResult::Sat(_) => 10,
+        SolveResult::Unsat => 20,
+        SolveResult::Unknown => 0,
+    }
+}
+
+/// Returns the literal if it is true in the last model, its negation if it is false,
+/// and 0 if its variable may take either value
+///
+/// # Safety
+/// The solver must come from `ipasir_init` and not have been released
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_val(solver: *mut c_void, lit_or_neg: i32) -> i32 {
+    let solver = self::solver(solver);
+    let lit = Literal::from(lit_or_neg);
+    let value = solver.solver.model()
+        .filter(|model| lit.id() < model.len())
+        .and_then(|model| model.value(lit.var()));
+    match value {
+        Some(value) if value == (lit_or_neg > 0) => lit_or_neg,
+        Some(_) => -lit_or_neg,
+        None => 0,
+    }
+}
+
+/// Returns 1 if the assumed literal is among the failed assumptions of the last solve
+///
+/// # Safety
+/// The solver must come from `ipasir_init` and not have been released
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_failed(solver: *mut c_void, lit: i32) -> c_int {
+    let solver = self::solver(solver);
+    solver.solver.failed().contains(&Literal::from(lit)) as c_int
+}
+
+/// The search gives up once the callback returns a nonzero value
+///
+/// # Safety
+/// The solver must come from `ipasir_init` and not have been released
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_set_terminate(solver: *mut c_void, data: *mut c_void,
+    terminate: Option<extern "C" fn(*mut c_void) -> c_int>) {
+    let solver = self::solver(solver);
+    let progress = terminate.map(|callback| {
+        let terminate = Terminate { data, callback };
+        let interrupt = Arc::clone(&solver.interrupt);
+        let report: ProgressCallback = Arc::new(move |_| {
+            if (terminate.callback)(terminate.data) != 0 {
+                interrupt.store(true, Ordering::Relaxed);
+            }
+        });
+        (TERMINATE_INTERVAL, report)
+    });
+    solver.solver.set_progress(progress);
+}
+
+/// Accepted for compatibility, but the callback is never called since the solver
+/// doesn't learn clauses
+///
+/// # Safety
+/// The solver must come from `ipasir_init` and not have been released
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_set_learn(_solver: *mut c_void, _data: *mut c_void,
+    _max_length: c_int, _learn: Option<extern "C" fn(*mut c_void, *mut i32)>) {}
@@ -0,0 +1,33 @@
+//! A simple DPLL-based SAT solver for formulas in conjunctive normal form. Without the
+//! default `std` feature the library is `no_std` and only needs `alloc`
+//!
+//! Solving is deterministic: randomized parts such as WalkSAT and chaos mode follow their
+//! seeds, and nothing depends on hash iteration order, so the same formula and settings
+//! give the same answer and counters on every run, unless a time limit or an interrupt
+//! cuts the search short
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod config;
+#[cfg(feature = "std")]
+pub mod export;
+pub mod generate;
+pub mod heuristic;
+#[cfg(feature = "ipasir")]
+mod ipasir;
+#[cfg(feature = "python")]
+mod python;
+mod rng;
+pub mod solver;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+pub use config::SolverConfig;
+pub use heuristic::{DecisionHeuristic, Heuristic, Phase};
+pub use solver::{Assignment, CardinalityConstraints, CardinalityEncoding, Comparison, Conflict,
+    CoreResult, CountingProblem, Engine, ExternalPropagator, Formula, Literal, LogCallback,
+    LogLevel, MaxSatAlgorithm, MaxSatResult, Models, ParseOptions, PbProblem, PreprocessStats,
+    ProgressCallback, SolveResult, Solver, Stats, Trace, TraceResult, Var, VarMap, WeightedFormula,
+    XorConstraints};
@@ -1,26 +1,1453 @@
 use std::env;
-use std::fs::File;
-use solver::Formula;
-use std::time::Instant;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use cache::AnswerCache;
+use simple_sat_solver::{export, generate, Assignment, Comparison, CoreResult, CountingProblem,
+    Engine, Formula, Heuristic, Literal, LogCallback, LogLevel, MaxSatAlgorithm, MaxSatResult,
+    ParseOptions, PbProblem, PreprocessStats, ProgressCallback, SolveResult, SolverConfig, Stats,
+    TraceResult, VarMap, WeightedFormula};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-mod solver;
+mod cache;
+mod eval;
+
+// Number of perturbed re-solves performed in chaos mode
+const CHAOS_RUNS: u64 = 8;
+// Flips made by WalkSAT before giving up, unless set with --max-flips
+const DEFAULT_MAX_FLIPS: u64 = 1_000_000;
+// Flips made when polishing a model against soft clauses
+const POLISH_FLIPS: u64 = 10_000;
+
+const USAGE: &str = "\
+Usage: simple-sat-solver [options] <input.cnf>
+       simple-sat-solver [options] <input.cnf|directory>...
+       simple-sat-solver split <input> --on <lit> -o <true-output> <false-output>
+       simple-sat-solver cube <input> --depth <depth> -o <output>
+       simple-sat-solver propagate <input> [-a <lit>]...
+       simple-sat-solver check-equiv <first> <second>
+       simple-sat-solver simplify <input> -o <output> [preprocessing options]
+       simple-sat-solver generate random --vars <n> [--k <k>] [--ratio <r>] [--seed <s>]
+       simple-sat-solver generate pigeonhole --holes <n>
+       simple-sat-solver generate parity --vars <n> [--seed <s>] [--unsat]
+
+Several inputs, or a directory of .cnf files, are solved one after the other with the
+search, preprocessing, --verify and --quiet options, printing a summary at the end.
+With --bench, so are the inputs of a single file or directory.
+
+Search:
+  --heuristic <ordered|jw|dlis>   Branching heuristic
+  --phase <positive|negative|occ> Polarity variables are tried in first
+  --hint <file>                   Warm-start from DIMACS literals, e.g. a previous model
+  --assume <lit>                  Solve under the assumed literal, may be repeated
+  --assume-weighted <lit> <weight> Assume the literal with a confidence weight, and if
+                                  unsatisfiable, print the lightest assumptions to drop
+  --cubes <file>                  Solve under each cube of an iCNF file
+  --walksat                       Use WalkSAT local search instead of DPLL
+  --max-flips <n>                 Flips before WalkSAT gives up
+  --seed <n>                      Seed for WalkSAT
+  --deterministic                 Make runs reproducible: no --timeout, and no time or
+                                  memory use in the output
+  --conflict-limit <n>            Give up with UNKNOWN after n conflicts
+  --propagation-limit <n>         Give up with UNKNOWN after n propagations
+  --timeout <seconds>             Give up with UNKNOWN after the given time
+  --threads <n>                   Race n differently configured searches, taking the
+                                  first answer
+
+Preprocessing:
+  --no-subsumption                Skip subsumption and self-subsuming resolution
+  --no-binary                     Skip binary implication graph simplification
+  --break-symmetries              Add symmetry-breaking clauses
+  --ternary <n>                   Split clauses longer than n into ternary ones
+  --fast-path-vars <n>            Skip subsumption and binary simplification on
+                                  formulas with fewer variables
+
+Problems:
+  --count                         Count the models, which a `c t mc` or `c t pmc`
+                                  comment of the input also asks for
+  --mus                           Print a minimal unsatisfiable subset
+  --delta <older.cnf>             Print a core among the clauses not in the older file
+  --soft <file>                   Polish the model to satisfy more of the soft clauses
+  --partial                       Leave don't-care variables out of the model
+  --maxsat                        Solve a WCNF file
+  --opb                           Solve an OPB file
+  --maxsat-algorithm <core|linear> MaxSAT algorithm for --maxsat and --opb
+  --xor                           Solve a CNF file with XOR clauses
+
+Output:
+  --proof <file>, --trace <file>  Write a TraceCheck proof if unsatisfiable
+  --stats                         Print preprocessing and search counters, and the time
+                                  spent in each phase
+  --bench <file>                  Write the result, time and counters of each input as CSV
+  --progress <n>                  Print the counters every n decisions
+  --log <level>                   Print the search to stderr at level quiet, normal (start
+                                  and result), verbose (conflicts) or debug (decisions)
+  --verify                        Check the model against the input clauses, or the hard
+                                  clauses with --maxsat
+  --no-model                      Print SATISFIABLE instead of the model
+  --complete-model <positive|negative> Give the variables the model leaves unassigned
+                                  this polarity, so it lists every variable
+  --quiet                         Leave out the time elapsed
+  --dump-vig <file>               Write the variable incidence graph
+  --dump-binary-implications <file> Write the binary implication graph
+  --cache <dir>                   Reuse answers for formulas solved before
+
+  --extend-vars                   Let clauses use more variables than the problem line
+                                  declares, instead of failing
+  --config <file>                 Read options from a file, as on the command line
+  -h, --help                      Print this help
+";
 
 fn main() -> Result<(), String> {
-    let file_name = env::args().nth(1)
-        .ok_or("Please provide an input file")?;
+    let mut args = env::args().skip(1).peekable();
+    match args.peek().map(String::as_str) {
+        Some("split") => {
+            args.next();
+            return split(args);
+        }
+        Some("cube") => {
+            args.next();
+            return cube(args);
+        }
+        Some("propagate") => {
+            args.next();
+            return propagate(args);
+        }
+        Some("check-equiv") => {
+            args.next();
+            return check_equiv(args);
+        }
+        Some("simplify") => {
+            args.next();
+            return simplify(args);
+        }
+        Some("generate") => {
+            args.next();
+            return generate(args);
+        }
+        _ => (),
+    }
+    let mut args = expand_config(args)?.into_iter();
+
+    let mut inputs = vec![];
+    let mut config = SolverConfig::default();
+    let mut chaos = false;
+    let mut verify = false;
+    let mut stats = false;
+    let mut bench_file = None;
+    let mut quiet = false;
+    let mut deterministic = false;
+    let mut no_model = false;
+    let mut complete_model = None;
+    let mut vig_file = None;
+    let mut older_file = None;
+    let mut mus = false;
+    let mut count = false;
+    let mut partial = false;
+    let mut maxsat = false;
+    let mut opb = false;
+    let mut xor = false;
+    let mut maxsat_algorithm = MaxSatAlgorithm::CoreGuided;
+    let mut trace_file = None;
+    let mut cache_dir = None;
+    let mut walk_sat = false;
+    let mut max_flips = DEFAULT_MAX_FLIPS;
+    let mut seed = 0;
+    let mut threads = 1;
+    let mut progress_interval = None;
+    let mut log_level = LogLevel::Quiet;
+    let mut cubes_file = None;
+    let mut soft_file = None;
+    let mut hint_file = None;
+    let mut implications_file = None;
+    let mut assumptions = vec![];
+    let mut weighted_assumptions = vec![];
+    let mut parse_options = ParseOptions::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--heuristic" => {
+                config.heuristic = args.next()
+                    .ok_or("Missing value for --heuristic")?
+                    .parse()?;
+            }
+            "--phase" => {
+                config.phase = args.next()
+                    .ok_or("Missing value for --phase")?
+                    .parse()?;
+            }
+            "--dump-vig" => {
+                vig_file = Some(args.next().ok_or("Missing value for --dump-vig")?);
+            }
+            "--dump-binary-implications" => {
+                let file = args.next().ok_or("Missing value for --dump-binary-implications")?;
+                implications_file = Some(file);
+            }
+            "--cache" => {
+                cache_dir = Some(args.next().ok_or("Missing value for --cache")?);
+            }
+            "--cubes" => {
+                cubes_file = Some(args.next().ok_or("Missing value for --cubes")?);
+            }
+            "--soft" => {
+                soft_file = Some(args.next().ok_or("Missing value for --soft")?);
+            }
+            "--hint" => {
+                hint_file = Some(args.next().ok_or("Missing value for --hint")?);
+            }
+            "--assume" => {
+                let value: isize = args.next()
+                    .ok_or("Missing value for --assume")?
+                    .parse()
+                    .map_err(|_| "Invalid value for --assume")?;
+                assumptions.push(value);
+            }
+            "--assume-weighted" => {
+                let value: isize = args.next()
+                    .ok_or("Missing value for --assume-weighted")?
+                    .parse()
+                    .map_err(|_| "Invalid value for --assume-weighted")?;
+                let weight: u64 = args.next()
+                    .ok_or("Missing weight for --assume-weighted")?
+                    .parse()
+                    .map_err(|_| "Invalid weight for --assume-weighted")?;
+                assumptions.push(value);
+                weighted_assumptions.push((weight, value));
+            }
+            "--trace" | "--proof" => {
+                trace_file = Some(args.next().ok_or(format!("Missing value for {}", arg))?);
+            }
+            "--delta" => {
+                older_file = Some(args.next().ok_or("Missing value for --delta")?);
+            }
+            "--fast-path-vars" => {
+                config.fast_path_vars = args.next()
+                    .ok_or("Missing value for --fast-path-vars")?
+                    .parse()
+                    .map_err(|_| "Invalid value for --fast-path-vars")?;
+            }
+            "--mus" => mus = true,
+            "--count" => count = true,
+            "--partial" => partial = true,
+            "--maxsat" => maxsat = true,
+            "--opb" => opb = true,
+            "--xor" => xor = true,
+            "--maxsat-algorithm" => {
+                maxsat_algorithm = args.next()
+                    .ok_or("Missing value for --maxsat-algorithm")?
+                    .parse()?;
+            }
+            "--no-subsumption" => config.subsumption = false,
+            "--no-binary" => config.binary = false,
+            "--break-symmetries" => config.break_symmetries = true,
+            "--ternary" => {
+                let threshold: usize = args.next()
+                    .ok_or("Missing value for --ternary")?
+                    .parse()
+                    .map_err(|_| "Invalid value for --ternary")?;
+                if threshold < 3 {
+                    return Err("The --ternary threshold must be at least 3".to_owned());
+                }
+                config.ternary_threshold = Some(threshold);
+            }
+            "--walksat" => walk_sat = true,
+            "--max-flips" => {
+                max_flips = args.next()
+                    .ok_or("Missing value for --max-flips")?
+                    .parse()
+                    .map_err(|_| "Invalid value for --max-flips")?;
+            }
+            "--conflict-limit" => {
+                let limit = args.next()
+                    .ok_or("Missing value for --conflict-limit")?
+                    .parse()
+                    .map_err(|_| "Invalid value for --conflict-limit")?;
+                config.conflict_limit = Some(limit);
+            }
+            "--propagation-limit" => {
+                let limit = args.next()
+                    .ok_or("Missing value for --propagation-limit")?
+                    .parse()
+                    .map_err(|_| "Invalid value for --propagation-limit")?;
+                config.propagation_limit = Some(limit);
+            }
+            "--timeout" => {
+                let seconds = args.next()
+                    .ok_or("Missing value for --timeout")?
+                    .parse()
+                    .ok()
+                    .and_then(|s: f64| Duration::try_from_secs_f64(s).ok())
+                    .ok_or("Invalid value for --timeout")?;
+                config.time_limit = Some(seconds);
+            }
+            "--threads" => {
+                threads = args.next()
+                    .ok_or("Missing value for --threads")?
+                    .parse()
+                    .ok()
+                    .filter(|&t: &usize| t > 0)
+                    .ok_or("Invalid value for --threads")?;
+            }
+            "--log" => {
+                log_level = args.next()
+                    .ok_or("Missing value for --log")?
+                    .parse()?;
+            }
+            "--progress" => {
+                let interval = args.next()
+                    .ok_or("Missing value for --progress")?
+                    .parse()
+                    .ok()
+                    .filter(|&i: &u64| i > 0)
+                    .ok_or("Invalid value for --progress")?;
+                progress_interval = Some(interval);
+            }
+            "--seed" => {
+                seed = args.next()
+                    .ok_or("Missing value for --seed")?
+                    .parse()
+                    .map_err(|_| "Invalid value for --seed")?;
+            }
+            "--stats" => stats = true,
+            "--bench" => {
+                bench_file = Some(args.next().ok_or("Missing value for --bench")?);
+            }
+            "--quiet" => quiet = true,
+            "--no-model" => no_model = true,
+            "--complete-model" => {
+                complete_model = match args.next().ok_or("Missing value for --complete-model")?
+                    .as_str() {
+                    "positive" => Some(true),
+                    "negative" => Some(false),
+                    polarity => return Err(format!("Unknown polarity '{}'", polarity)),
+                };
+            }
+            "--verify" => verify = true,
+            "--deterministic" => deterministic = true,
+            // Hidden developer mode, see check_chaos
+            "--chaos" => chaos = true,
+            "--extend-vars" => parse_options.extend_vars = true,
+            "--help" | "-h" => {
+                print!("{}", USAGE);
+                return Ok(());
+            }
+            _ if arg.starts_with('-') => {
+                return Err(format!("Unknown option '{}', see --help", arg));
+            }
+            _ => inputs.push(arg),
+        }
+    }
+
+    if walk_sat {
+        config.engine = Engine::WalkSat { max_flips, seed };
+    }
+    // The search itself only depends on the seeds, so what is left is to do without the
+    // clock, and to leave out the times and memory use, which differ between runs
+    if deterministic {
+        if config.time_limit.is_some() {
+            return Err("--timeout depends on the speed of the machine, so it can't be \
+                combined with --deterministic".to_owned());
+        }
+        if threads > 1 {
+            return Err("With --threads the fastest search answers, so it can't be combined \
+                with --deterministic".to_owned());
+        }
+        quiet = true;
+    }
+    if partial && complete_model.is_some() {
+        return Err("--partial leaves variables out of the model, so it can't be combined with \
+            --complete-model".to_owned());
+    }
+    let search_only = !assumptions.is_empty() || cubes_file.is_some() || soft_file.is_some();
+    if search_only && (mus || older_file.is_some() || trace_file.is_some()) {
+        return Err("--mus, --delta and --trace work on the clauses alone, so they can't be \
+            combined with --assume, --cubes or --soft".to_owned());
+    }
+    let heuristic = config.heuristic;
+    let batch = inputs.len() > 1 || inputs.first().is_some_and(|input| Path::new(input).is_dir());
+    // These refer to the variables by their numbers in the input, or depend on how many
+    // variables it has, so the variables which don't occur can't be left out
+    let numbered = count || mus || trace_file.is_some() || older_file.is_some()
+        || cubes_file.is_some() || soft_file.is_some() || hint_file.is_some()
+        || vig_file.is_some() || implications_file.is_some() || cache_dir.is_some()
+        || !assumptions.is_empty();
+    if batch || bench_file.is_some() {
+        let single_only = maxsat || opb || xor || partial || chaos || numbered
+            || parse_options.extend_vars || complete_model.is_some();
+        if single_only {
+            return Err("Batch mode only supports the search and preprocessing options".to_owned());
+        }
+        if inputs.is_empty() {
+            return Err("Please provide an input file, see --help".to_owned());
+        }
+        return solve_batch(&inputs, &config, threads, verify, quiet, bench_file);
+    }
+    let file_name = inputs.pop().ok_or("Please provide an input file, see --help")?;
+
+    if verify && (xor || opb) {
+        return Err("--verify checks the model against clauses, so it can't be combined with \
+            --xor or --opb".to_owned());
+    }
+    if maxsat {
+        return solve_maxsat(&file_name, maxsat_algorithm, &config, verify);
+    }
+    if opb {
+        return solve_opb(&file_name, maxsat_algorithm, &config);
+    }
+    if xor {
+        return solve_xor(&file_name, heuristic, quiet, no_model);
+    }
+    // A `c t` comment of the input declares a model counting problem, which is then
+    // solved and printed as in the model counting competition
+    let counting = read_counting_problem(&file_name)?;
+    if counting == Some(CountingProblem::WeightedModels) {
+        return Err("The input asks for weighted model counting (c t wmc), which isn't \
+            supported".to_owned());
+    }
+    let numbered = numbered || counting.is_some();
+
+    let start = Instant::now();
+
+    // Otherwise the search only sees the variables which occur, renamed consecutively,
+    // and the clauses without duplicates, and the model is mapped back to the input's
+    // numbering
+    let (mut formula, var_map) = if numbered {
+        (open_formula(&file_name, parse_options)?, None)
+    } else {
+        let (formula, var_map) = open_compact_formula(&file_name, parse_options.dedup(true))?;
+        (formula, Some(var_map))
+    };
+    // The time of each phase, printed with the counters unless the run is deterministic
+    let timed = stats && !deterministic;
+    let mut phase_times = vec![("Parsing", start.elapsed())];
+    formula.configure(&config);
+    if let Some(hint_file) = hint_file {
+        let hint = read_hint(&formula, &hint_file)?;
+        formula.set_hint(Some(hint));
+    }
+    if let Some(interval) = progress_interval {
+        let report: ProgressCallback = Arc::new(|stats| {
+            eprintln!("c decisions: {} conflicts: {}", stats.decisions, stats.conflicts);
+        });
+        formula.set_progress(Some((interval, report)));
+    }
+    if log_level > LogLevel::Quiet {
+        let log: LogCallback = Arc::new(|line| eprintln!("c {}", line));
+        formula.set_log(Some((log_level, log)));
+    }
+    // Chaos runs check the models of the formula searched, while --verify checks the
+    // model printed against the clauses as read, before any variables are renumbered
+    let chaos_clauses = if chaos { dimacs_clauses(&formula) } else { vec![] };
+    let input_clauses = match (verify, &var_map) {
+        (false, _) => vec![],
+        (true, None) => dimacs_clauses(&formula),
+        (true, Some(_)) => dimacs_clauses(&open_formula(&file_name, parse_options)?),
+    };
+    if older_file.is_some() || mus {
+        let core_start = Instant::now();
+        let core = match older_file {
+            Some(older_file) => {
+                let older = open_formula(&older_file, parse_options)?;
+                formula.unsat_delta(&older, heuristic.build().as_mut())
+            }
+            None => formula.minimal_unsat_subset(heuristic.build().as_mut()),
+        };
+        phase_times.push(("Core extraction", core_start.elapsed()));
+        print_core(core)?;
+        if timed {
+            print_phase_times(&phase_times);
+        }
+        return Ok(());
+    }
+    if let Some(trace_file) = trace_file {
+        // The trace refers to the input clauses, so none of them may be rewritten
+        let search_start = Instant::now();
+        let result = formula.solve_traced(heuristic.build().as_mut());
+        phase_times.push(("Search", search_start.elapsed()));
+        match result {
+            TraceResult::Sat(model) => {
+                let model = match complete_model {
+                    Some(value) => model.completed(value),
+                    None => model,
+                };
+                if verify {
+                    check_model(&input_clauses, &model)?;
+                }
+                print_answer(&format!("{}\n", model), no_model);
+            }
+            TraceResult::Unsat(trace) => {
+                let proof_start = Instant::now();
+                write_file(&trace_file, |out| export::write_tracecheck(&trace, out))?;
+                phase_times.push(("Proof", proof_start.elapsed()));
+                println!("UNSATISFIABLE");
+            }
+            TraceResult::Unknown => println!("UNKNOWN"),
+        }
+        if timed {
+            print_phase_times(&phase_times);
+        }
+        if !quiet {
+            println!("Time elapsed: {:?}", start.elapsed());
+        }
+        return Ok(());
+    }
+    if let Some(vig_file) = vig_file {
+        write_file(&vig_file, |out| export::write_vig(&formula, out))?;
+    }
+    if let Some(implications_file) = implications_file {
+        let mut simplified = formula.clone();
+        simplified.propagate_units();
+        simplified.subsume();
+        write_file(&implications_file, |out| export::write_binary_implications(&simplified, out))?;
+    }
+    // Solving under assumptions means solving with them as unit clauses, but finding
+    // the failed ones needs the formula without them
+    let mut unrestricted = if assumptions.is_empty() {
+        None
+    } else {
+        check_literals(&formula, &assumptions)?;
+        let assumptions: Vec<_> = assumptions.iter().map(|&v| Literal::from_var(v)).collect();
+        let restricted = formula.restricted(&assumptions);
+        Some((std::mem::replace(&mut formula, restricted), assumptions))
+    };
+    // Preprocessing may drop models, so count them on the input clauses
+    if count || counting.is_some() {
+        let (models, problem_type) = match counting {
+            Some(CountingProblem::ProjectedModels(vars)) => {
+                (formula.count_projected_models(&vars, heuristic.build().as_mut()), Some("pmc"))
+            }
+            Some(_) => (formula.count_models(), Some("mc")),
+            None => (formula.count_models(), None),
+        };
+        let models = models.ok_or("The model count doesn't fit in 128 bits")?;
+        match problem_type {
+            Some(problem_type) => print_count(problem_type, models),
+            None => println!("Models: {}", models),
+        }
+        if !quiet {
+            println!("Time elapsed: {:?}", start.elapsed());
+        }
+        return Ok(());
+    }
+    // Polishing needs the clauses as they were before preprocessing rewrites them
+    let soft = soft_file.map(|soft_file| {
+        let soft = open_formula(&soft_file, parse_options)?;
+        if soft.num_vars() > formula.num_vars() {
+            return Err("Soft clauses use variables outside the formula".to_owned());
+        }
+        Ok((soft, formula.clone()))
+    }).transpose()?;
+    // Shrinking checks the model against the clauses before preprocessing too
+    let original = if partial { Some(formula.clone()) } else { None };
+    // The cache holds the result of the search, which the output options below are
+    // applied to as if it had just been found
+    let cache = cache_dir.map(AnswerCache::open).transpose()?;
+    let fingerprint = formula.fingerprint();
+    let cached = cache.as_ref()
+        .and_then(|c| c.lookup(fingerprint))
+        .and_then(|answer| cached_result(&formula, &answer));
+    let hit = cached.is_some();
+    // The first Ctrl-C makes the search give up, and the answer it has by then, UNKNOWN
+    // unless it was done, is printed with the counters. The second exits right away,
+    // printing UNKNOWN unless the answer is out. Either way the exit code is 130
+    let interrupt = Arc::new(AtomicBool::new(false));
+    let answered = Arc::new(AtomicBool::new(false));
+
+    let (result, search_stats) = match cached {
+        Some(result) => (result, None),
+        None => {
+            let preprocess_start = Instant::now();
+            let preprocessed = formula.preprocess(&config);
+            phase_times.push(("Preprocessing", preprocess_start.elapsed()));
+            if stats {
+                print_preprocess_stats(&preprocessed);
+            }
+            if chaos {
+                let base_seed = if deterministic { Some(seed) } else { None };
+                check_chaos(&formula, &chaos_clauses, heuristic, base_seed);
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let (flag, answered) = (Arc::clone(&interrupt), Arc::clone(&answered));
+                ctrlc::set_handler(move || {
+                    if flag.swap(true, Ordering::Relaxed) {
+                        // Waits for an answer being printed to be finished
+                        let mut stdout = io::stdout().lock();
+                        if !answered.load(Ordering::Relaxed) {
+                            let _ = writeln!(stdout, "UNKNOWN");
+                        }
+                        process::exit(130);
+                    }
+                }).map_err(|_| "Failed to set the Ctrl-C handler")?;
+            }
+            formula.set_interrupt(Some(Arc::clone(&interrupt)));
+            if let Some((unrestricted, _)) = &mut unrestricted {
+                unrestricted.set_interrupt(Some(Arc::clone(&interrupt)));
+            }
+            let search_start = Instant::now();
+            let (result, search_stats) = match cubes_file {
+                Some(_) if threads > 1 => {
+                    return Err("--threads can't be combined with --cubes".to_owned());
+                }
+                Some(cubes_file) => {
+                    let file = File::open(&cubes_file)
+                        .map_err(|_| format!("Failed to open {}", cubes_file))?;
+                    let cubes = formula.parse_cubes(file)?;
+                    (formula.solve_cubes(&cubes, heuristic.build().as_mut()), None)
+                }
+                None => {
+                    let (result, search_stats) = solve_search(formula, heuristic, threads);
+                    (result, Some(search_stats))
+                }
+            };
+            phase_times.push(("Search", search_start.elapsed()));
+            if let Some(cache) = &cache {
+                let answer = match &result {
+                    SolveResult::Sat(model) => Some(format!("{}\n", model)),
+                    SolveResult::Unsat => Some("UNSATISFIABLE\n".to_owned()),
+                    SolveResult::Unknown => None,
+                };
+                if let Some(answer) = answer {
+                    cache.store(fingerprint, &answer)?;
+                }
+            }
+            (result, search_stats)
+        }
+    };
+    // Polishing, shrinking and restoring the model, or finding the failed assumptions
+    let post_start = Instant::now();
+    let result = match (result, &soft) {
+        (SolveResult::Sat(model), Some((soft, original))) => {
+            let (polished, satisfied) = original.polish(&model, soft, POLISH_FLIPS);
+            println!("Satisfied soft clauses: {}/{}", satisfied, soft.clauses().count());
+            SolveResult::Sat(polished)
+        }
+        (result, _) => result,
+    };
+    let result = match (result, &original) {
+        (SolveResult::Sat(model), Some(original)) => {
+            SolveResult::Sat(original.shrink_model(&model))
+        }
+        (result, _) => result,
+    };
+    let result = match (result, &var_map) {
+        (SolveResult::Sat(model), Some(var_map)) => SolveResult::Sat(var_map.restore(&model)),
+        (result, _) => result,
+    };
+    let result = match (result, complete_model) {
+        (SolveResult::Sat(model), Some(value)) => SolveResult::Sat(model.completed(value)),
+        (result, _) => result,
+    };
+    if let (SolveResult::Sat(model), true) = (&result, verify) {
+        check_model(&input_clauses, model)?;
+    }
+    let answer = match &result {
+        SolveResult::Sat(a) => format!("{}\n", a),
+        SolveResult::Unsat => match &unrestricted {
+            Some((unrestricted, assumptions)) => {
+                match unrestricted.failed_assumptions(assumptions, heuristic.build().as_mut()) {
+                    CoreResult::Minimal(failed) => {
+                        let failed: String =
+                            failed.iter().map(|l| format!("{} ", l.to_var())).collect();
+                        let mut answer = format!("UNSATISFIABLE\nFailed assumptions: {}0\n",
+                            failed);
+                        if !weighted_assumptions.is_empty() {
+                            answer += &assumptions_to_drop(unrestricted, assumptions,
+                                &weighted_assumptions, maxsat_algorithm, heuristic);
+                        }
+                        answer
+                    }
+                    CoreResult::Sat => {
+                        return Err("Internal error: the assumptions are satisfiable".to_owned());
+                    }
+                    // The limits ran out while minimizing, after the search had finished
+                    CoreResult::Unknown => {
+                        "UNSATISFIABLE\nFailed assumptions: unknown\n".to_owned()
+                    }
+                }
+            }
+            None => "UNSATISFIABLE\n".to_owned(),
+        },
+        SolveResult::Unknown => "UNKNOWN\n".to_owned(),
+    };
+    phase_times.push(("Post-processing", post_start.elapsed()));
+    // Holding stdout keeps a second Ctrl-C from printing in the middle of the answer
+    let stdout = io::stdout().lock();
+    answered.store(true, Ordering::Relaxed);
+    print_answer(&answer, no_model);
+    if let (SolveResult::Sat(a), true) = (&result, stats) {
+        println!("Model fingerprint: {:016x}", a.fingerprint());
+    }
+    // A search which gave up reports how far it got
+    let interrupted = interrupt.load(Ordering::Relaxed);
+    let gave_up = matches!(result, SolveResult::Unknown) || interrupted;
+    if let (Some(search_stats), true) = (search_stats, stats || gave_up) {
+        println!("Decisions: {}", search_stats.decisions);
+        println!("Conflicts: {}", search_stats.conflicts);
+        println!("Propagations: {}", search_stats.propagations);
+        println!("Duplicate clauses: {}", search_stats.duplicate_clauses);
+        if let (Some(peak_memory_kb), false) = (search_stats.peak_memory_kb, deterministic) {
+            println!("Peak memory: {} kB", peak_memory_kb);
+        }
+    }
+    if timed {
+        print_phase_times(&phase_times);
+    }
+
+    if !quiet {
+        println!("Time elapsed: {:?}{}", start.elapsed(), if hit { " (cached)" } else { "" });
+    }
+    drop(stdout);
+    if interrupted {
+        process::exit(130);
+    }
+
+    Ok(())
+}
+
+// The result of the search stored in the cache for the formula, unless it doesn't hold
+// up: fingerprints of different formulas can collide, so a model must satisfy the
+// clauses. A collision with an unsatisfiable formula goes unnoticed, but is as unlikely
+// as any two of 2^64 values being equal
+fn cached_result(formula: &Formula, answer: &str) -> Option<SolveResult> {
+    if answer == "UNSATISFIABLE\n" {
+        return Some(SolveResult::Unsat);
+    }
+    let mut values = vec![None; formula.num_vars()];
+    for v in answer.split_whitespace() {
+        let v: isize = v.parse().ok()?;
+        if v == 0 {
+            break;
+        }
+        *values.get_mut(v.unsigned_abs() - 1)? = Some(v > 0);
+    }
+    let model = Assignment::from(values);
+    let satisfied = eval::first_falsified(&dimacs_clauses(formula), &model.completed_values())
+        .is_none();
+    satisfied.then_some(SolveResult::Sat(model))
+}
+
+// Replaces each `--config <file>` by the options in the file, which are written as on the
+// command line, with lines starting with # left out
+fn expand_config(mut args: impl Iterator<Item = String>) -> Result<Vec<String>, String> {
+    let mut expanded = vec![];
+    while let Some(arg) = args.next() {
+        if arg != "--config" {
+            expanded.push(arg);
+            continue;
+        }
+        let file_name = args.next().ok_or("Missing value for --config")?;
+        let input = fs::read_to_string(&file_name)
+            .map_err(|_| format!("Failed to open {}", file_name))?;
+        for line in input.lines().filter(|l| !l.trim_start().starts_with('#')) {
+            for option in line.split_whitespace() {
+                if option == "--config" {
+                    return Err("Config files can't include other config files".to_owned());
+                }
+                expanded.push(option.to_owned());
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+// Prints the answer, with a model replaced by SATISFIABLE if it isn't wanted
+fn print_answer(answer: &str, no_model: bool) {
+    if no_model && answer.starts_with(|c: char| c == '-' || c.is_ascii_digit()) {
+        println!("SATISFIABLE");
+    } else {
+        print!("{}", answer);
+    }
+}
+
+fn open_formula(file_name: &str, options: ParseOptions) -> Result<Formula, String> {
+    let file = File::open(file_name)
+        .map_err(|_| format!("Failed to open {}", file_name))?;
+    Formula::parse_dimacs_with(file, options)
+}
+
+// Reads the counting problem which the input declares, see
+// `Formula::parse_counting_problem_str`, from its lines up to the problem line
+fn read_counting_problem(file_name: &str) -> Result<Option<CountingProblem>, String> {
+    let file = File::open(file_name)
+        .map_err(|_| format!("Failed to open {}", file_name))?;
+    let mut header = String::new();
+    for line in BufReader::new(file).split(b'\n') {
+        let line = line.map_err(|_| "Error while reading file")?;
+        header.push_str(&String::from_utf8_lossy(&line));
+        header.push('\n');
+        if !line.starts_with(b"c") {
+            break;
+        }
+    }
+    Formula::parse_counting_problem_str(&header)
+}
+
+// Opens the formula with only the variables which occur in it, see
+// `Formula::parse_dimacs_compact`
+fn open_compact_formula(file_name: &str, options: ParseOptions)
+    -> Result<(Formula, VarMap), String> {
+    let file = File::open(file_name)
+        .map_err(|_| format!("Failed to open {}", file_name))?;
+    Formula::parse_dimacs_compact(file, options)
+}
+
+// Reads an assignment to warm-start from, given as DIMACS literals such as the v lines
+// printed for a model, leaving out c and s lines
+fn read_hint(formula: &Formula, file_name: &str) -> Result<Assignment, String> {
+    let input = fs::read_to_string(file_name)
+        .map_err(|_| format!("Failed to open {}", file_name))?;
+    let mut vars = vec![];
+    for line in input.lines().filter(|l| !l.starts_with('c') && !l.starts_with('s')) {
+        for v in line.trim_start_matches('v').split_whitespace() {
+            let v: isize = v.parse().map_err(|_| format!("Illegal variable '{}'", v))?;
+            if v != 0 {
+                vars.push(v);
+            }
+        }
+    }
+    check_literals(formula, &vars)?;
+    let mut values = vec![None; formula.num_vars()];
+    for v in vars {
+        values[v.unsigned_abs() - 1] = Some(v > 0);
+    }
+    Ok(Assignment::from(values))
+}
+
+// Makes the searches of the formula give up at the limits of the configuration
+fn set_limits(formula: &mut Formula, config: &SolverConfig) {
+    formula.set_conflict_limit(config.conflict_limit);
+    formula.set_propagation_limit(config.propagation_limit);
+    formula.set_time_limit(config.time_limit);
+}
+
+// Solves each input file in turn, where directories stand for the .cnf files in them,
+// printing a line with the result of each and a summary of them all. With a bench file,
+// the result, time and counters of each instance are also written to it as CSV
+fn solve_batch(inputs: &[String], config: &SolverConfig, threads: usize, verify: bool,
+    quiet: bool, bench_file: Option<String>) -> Result<(), String> {
+    let mut files = vec![];
+    for input in inputs {
+        if !Path::new(input).is_dir() {
+            files.push(input.clone());
+            continue;
+        }
+        let entries = fs::read_dir(input).map_err(|_| format!("Failed to read {}", input))?;
+        let mut cnf_files: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|e| e == "cnf"))
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        cnf_files.sort();
+        files.extend(cnf_files);
+    }
+
+    let start = Instant::now();
+    let (mut sat, mut unsat, mut unknown, mut errors) = (0, 0, 0, 0);
+    let mut rows = vec![];
+    for file_name in &files {
+        let file_start = Instant::now();
+        let outcome = solve_instance(file_name, config, threads, verify);
+        let elapsed = file_start.elapsed();
+        let (status, stats) = match &outcome {
+            Ok((SolveResult::Sat(_), stats)) => {
+                sat += 1;
+                ("SATISFIABLE", Some(stats))
+            }
+            Ok((SolveResult::Unsat, stats)) => {
+                unsat += 1;
+                ("UNSATISFIABLE", Some(stats))
+            }
+            Ok((SolveResult::Unknown, stats)) => {
+                unknown += 1;
+                ("UNKNOWN", Some(stats))
+            }
+            Err(_) => {
+                errors += 1;
+                ("ERROR", None)
+            }
+        };
+        let line = match &outcome {
+            Err(error) => format!("{}: ERROR {}", file_name, error),
+            Ok(_) => format!("{}: {}", file_name, status),
+        };
+        if quiet {
+            println!("{}", line);
+        } else {
+            println!("{} ({:?})", line, elapsed);
+        }
+        if bench_file.is_some() {
+            // Errors leave the counters empty, as does a platform without memory reporting
+            let counters = match stats {
+                Some(stats) => format!("{},{},{}", stats.decisions, stats.conflicts,
+                    stats.peak_memory_kb.map_or(String::new(), |kb| kb.to_string())),
+                None => ",,".to_owned(),
+            };
+            rows.push(format!("{},{},{:.6},{}", csv_field(file_name), status,
+                elapsed.as_secs_f64(), counters));
+        }
+    }
+    println!("Solved: {}/{} (SAT: {}, UNSAT: {}), Unknown: {}, Errors: {}",
+        sat + unsat, files.len(), sat, unsat, unknown, errors);
+    if !quiet {
+        println!("Time elapsed: {:?}", start.elapsed());
+    }
+    if let Some(bench_file) = bench_file {
+        write_file(&bench_file, |out| {
+            writeln!(out, "instance,result,time_s,decisions,conflicts,peak_memory_kb")?;
+            rows.iter().try_for_each(|row| writeln!(out, "{}", row))
+        })?;
+    }
+    Ok(())
+}
+
+// Quotes a CSV field if it contains a separator, a quote or a line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+// Preprocesses and solves a DIMACS file as configured, within the conflict, propagation
+// and time limits, checking the model against the input clauses if asked to
+fn solve_instance(file_name: &str, config: &SolverConfig, threads: usize, verify: bool)
+    -> Result<(SolveResult, Stats), String> {
+    let parse_options = ParseOptions::default().dedup(true);
+    let (mut formula, var_map) = open_compact_formula(file_name, parse_options)?;
+    formula.configure(config);
+    formula.preprocess(config);
+    let (result, stats) = solve_search(formula, config.heuristic, threads);
+    if let (SolveResult::Sat(model), true) = (&result, verify) {
+        let input = open_formula(file_name, ParseOptions::default())?;
+        check_model(&dimacs_clauses(&input), &var_map.restore(model))?;
+    }
+    Ok((result, stats))
+}
+
+// Solves the formula with the heuristic, by a portfolio if there are several threads
+fn solve_search(formula: Formula, heuristic: Heuristic, threads: usize) -> (SolveResult, Stats) {
+    if threads > 1 {
+        formula.solve_portfolio(heuristic, threads)
+    } else {
+        formula.solve_with_stats(heuristic.build().as_mut())
+    }
+}
+
+// Solves a WCNF file and prints the result in the format of the MaxSAT Evaluation,
+// i.e. the cost on an o line, the status on an s line and the model on a v line. If
+// asked to, the model is checked against the hard clauses
+fn solve_maxsat(file_name: &str, algorithm: MaxSatAlgorithm, config: &SolverConfig,
+    verify: bool) -> Result<(), String> {
+    let heuristic = config.heuristic;
+    let file = File::open(file_name)
+        .map_err(|_| format!("Failed to open {}", file_name))?;
+    let mut formula = WeightedFormula::parse_wcnf(file)?;
+    set_limits(formula.hard(), config);
+    let hard_clauses = if verify { dimacs_clauses(formula.hard()) } else { vec![] };
+    let solution = formula.solve_maxsat_with(algorithm, heuristic.build().as_mut());
+    let status = optimization_status(&solution);
+    match solution {
+        MaxSatResult::Optimum(model, cost) | MaxSatResult::Best(model, cost) => {
+            if verify {
+                check_model(&hard_clauses, &model)?;
+            }
+            let values: String = model.completed_values().iter()
+                .map(|&value| if value { '1' } else { '0' })
+                .collect();
+            println!("o {}", cost);
+            println!("s {}", status);
+            println!("v {}", values);
+        }
+        MaxSatResult::Unsat => println!("s UNSATISFIABLE"),
+        MaxSatResult::Unknown => println!("s UNKNOWN"),
+    }
+    Ok(())
+}
+
+// Reports the lightest set of the weighted assumptions to drop so that the formula is
+// satisfiable under the others, which are kept along with the unweighted ones
+fn assumptions_to_drop(unrestricted: &Formula, assumptions: &[Literal],
+    weighted: &[(u64, isize)], algorithm: MaxSatAlgorithm, heuristic: Heuristic) -> String {
+    let weighted: Vec<_> = weighted.iter().map(|&(w, v)| (w, Literal::from_var(v))).collect();
+    let fixed: Vec<_> = assumptions.iter()
+        .filter(|&&lit| !weighted.iter().any(|&(_, l)| l == lit))
+        .copied()
+        .collect();
+    let dropped = |model: &Assignment| -> String {
+        weighted.iter()
+            .filter(|&&(_, lit)| model.value(lit.var()) != Some(!lit.is_negated()))
+            .map(|(_, lit)| format!("{} ", lit.to_var()))
+            .collect()
+    };
+    let restricted = unrestricted.restricted(&fixed);
+    match restricted.assumptions_to_drop(&weighted, algorithm, heuristic.build().as_mut()) {
+        MaxSatResult::Optimum(model, cost) => {
+            format!("Assumptions to drop (weight {}): {}0\n", cost, dropped(&model))
+        }
+        MaxSatResult::Best(model, cost) => {
+            format!("Assumptions to drop (weight {}, perhaps not the lightest): {}0\n", cost,
+                dropped(&model))
+        }
+        MaxSatResult::Unsat => "Assumptions to drop: none suffice\n".to_owned(),
+        MaxSatResult::Unknown => "Assumptions to drop: unknown\n".to_owned(),
+    }
+}
+
+// The status line for a solution, which is only optimal if the search finished
+fn optimization_status(solution: &MaxSatResult) -> &'static str {
+    match solution {
+        MaxSatResult::Optimum(..) => "OPTIMUM FOUND",
+        MaxSatResult::Best(..) => "SATISFIABLE",
+        MaxSatResult::Unsat => "UNSATISFIABLE",
+        MaxSatResult::Unknown => "UNKNOWN",
+    }
+}
+
+// Solves an OPB file and prints the result in the format of the Pseudo-Boolean
+// Competition. Problems with an objective are optimized with the MaxSAT algorithm
+fn solve_opb(file_name: &str, algorithm: MaxSatAlgorithm, config: &SolverConfig)
+    -> Result<(), String> {
+    let heuristic = config.heuristic;
     let file = File::open(file_name)
-        .map_err(|_| "Failed to open file")?;
+        .map_err(|_| format!("Failed to open {}", file_name))?;
+    let problem = PbProblem::parse_opb(file)?;
+    let model = if problem.objective().is_some() {
+        let mut weighted = problem.to_weighted();
+        set_limits(weighted.hard(), config);
+        let solution = weighted.solve_maxsat_with(algorithm, heuristic.build().as_mut());
+        let status = optimization_status(&solution);
+        match solution {
+            MaxSatResult::Optimum(model, _) | MaxSatResult::Best(model, _) => {
+                println!("o {}", problem.objective_value(&model));
+                println!("s {}", status);
+                Some(model)
+            }
+            MaxSatResult::Unsat => None,
+            MaxSatResult::Unknown => {
+                println!("s UNKNOWN");
+                return Ok(());
+            }
+        }
+    } else {
+        let mut formula = problem.formula().clone();
+        set_limits(&mut formula, config);
+        match formula.solve(heuristic.build().as_mut()) {
+            SolveResult::Sat(model) => {
+                println!("s SATISFIABLE");
+                Some(model)
+            }
+            SolveResult::Unsat => None,
+            SolveResult::Unknown => {
+                println!("s UNKNOWN");
+                return Ok(());
+            }
+        }
+    };
+    match model {
+        Some(model) => {
+            let values: Vec<_> = model.completed_values().iter()
+                .take(problem.num_vars())
+                .enumerate()
+                .map(|(id, &value)| format!("{}x{}", if value { "" } else { "-" }, id + 1))
+                .collect();
+            println!("v {}", values.join(" "));
+        }
+        None => println!("s UNSATISFIABLE"),
+    }
+    Ok(())
+}
 
+// Solves a DIMACS file with XOR clauses, which are handled by Gaussian elimination
+fn solve_xor(file_name: &str, heuristic: Heuristic, quiet: bool, no_model: bool)
+    -> Result<(), String> {
     let start = Instant::now();
+    let file = File::open(file_name)
+        .map_err(|_| format!("Failed to open {}", file_name))?;
+    let (formula, mut xors) = Formula::parse_dimacs_xor(file)?;
+    match formula.solve_with_propagator(heuristic.build().as_mut(), &mut xors) {
+        SolveResult::Sat(model) => print_answer(&format!("{}\n", model), no_model),
+        SolveResult::Unsat => println!("UNSATISFIABLE"),
+        SolveResult::Unknown => println!("UNKNOWN"),
+    }
+    if !quiet {
+        println!("Time elapsed: {:?}", start.elapsed());
+    }
+    Ok(())
+}
+
+// Creates the file and fills it using the given function
+fn write_file(file_name: &str, write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>)
+    -> Result<(), String> {
+    let file = File::create(file_name)
+        .map_err(|_| format!("Failed to create {}", file_name))?;
+    let mut out = BufWriter::new(file);
+    write(&mut out)
+        .and_then(|_| out.flush())
+        .map_err(|_| format!("Failed to write {}", file_name))
+}
 
-    let formula = Formula::parse_dimacs(file)?;
-    match formula.solve() {
-        Some(a) => println!("{}", a),
-        None => println!("UNSATISFIABLE"),
+// Runs `split <input> --on <var> -o <true-output> <false-output>`, which writes the
+// formula simplified with the DIMACS literal asserted and with it negated respectively
+fn split(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut file_name = None;
+    let mut var = None;
+    let mut outputs = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--on" => {
+                let value: isize = args.next()
+                    .ok_or("Missing value for --on")?
+                    .parse()
+                    .map_err(|_| "Invalid value for --on")?;
+                var = Some(value);
+            }
+            "-o" => {
+                let true_file = args.next().ok_or("Missing output files for -o")?;
+                let false_file = args.next().ok_or("Missing second output file for -o")?;
+                outputs = Some((true_file, false_file));
+            }
+            _ => file_name = Some(arg),
+        }
     }
 
-    let duration = start.elapsed();
-    println!("Time elapsed: {:?}", duration);
+    let formula = open_formula(&file_name.ok_or("Please provide an input file")?,
+        ParseOptions::default())?;
+    let var = var.ok_or("Please provide a literal to split on with --on")?;
+    let (true_file, false_file) = outputs.ok_or("Please provide output files with -o")?;
+    check_literals(&formula, &[var])?;
+
+    let (asserted, negated) = formula.split_on(Literal::from_var(var));
+    write_file(&true_file, |out| export::write_dimacs(&asserted, out))?;
+    write_file(&false_file, |out| export::write_dimacs(&negated, out))
+}
+
+// Runs `simplify <input> -o <output>`, which writes the formula after unit propagation
+// and the preprocessing steps not disabled by the options. The variables of the input
+// keep their numbers, and any further ones are auxiliary, so restricting a model of the
+// output to the variables of the input gives a model of the input. No reconstruction is
+// needed for that: substituted variables keep the clauses tying them to their
+// representatives, and a variable which no longer occurs can take either value in the
+// input too. Output which would drop input variables is refused, since a model of it
+// couldn't be mapped back
+fn simplify(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut file_name = None;
+    let mut output = None;
+    // Simplifying is all that is asked for, so small formulas aren't skipped
+    let mut config = SolverConfig::default().fast_path_vars(0);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" => output = Some(args.next().ok_or("Missing output file for -o")?),
+            "--no-subsumption" => config.subsumption = false,
+            "--no-binary" => config.binary = false,
+            "--break-symmetries" => config.break_symmetries = true,
+            "--ternary" => {
+                let threshold: usize = args.next()
+                    .ok_or("Missing value for --ternary")?
+                    .parse()
+                    .map_err(|_| "Invalid value for --ternary")?;
+                if threshold < 3 {
+                    return Err("The --ternary threshold must be at least 3".to_owned());
+                }
+                config.ternary_threshold = Some(threshold);
+            }
+            _ => file_name = Some(arg),
+        }
+    }
+
+    let file_name = file_name.ok_or("Please provide an input file")?;
+    let mut formula = open_formula(&file_name, ParseOptions::default())?;
+    let output = output.ok_or("Please provide an output file with -o")?;
+    let (num_vars, num_clauses) = (formula.num_vars(), formula.clauses().count());
+
+    formula.propagate_units();
+    let stats = formula.preprocess(&config);
+    print_preprocess_stats(&stats);
+    if formula.num_vars() < num_vars {
+        return Err(format!("Internal error: preprocessing dropped variables {} to {}",
+            formula.num_vars() + 1, num_vars));
+    }
+    println!("Variables: {} -> {}", num_vars, formula.num_vars());
+    println!("Clauses: {} -> {}", num_clauses, formula.clauses().count());
+    write_file(&output, |out| {
+        writeln!(out, "c Simplified from {} by simple-sat-solver", file_name)?;
+        writeln!(out, "c Variables 1 to {} are those of the input, and restricting a model to \
+            them gives a model of the input", num_vars)?;
+        writeln!(out, "c Variables of the input which no longer occur in the clauses can take \
+            either value")?;
+        export::write_dimacs(&formula, out)
+    })
+}
+
+// Runs `generate <family> [-o <output>]` with the parameters of the family, which writes
+// a formula of the family to the output, or prints it if there is none
+fn generate(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let family = args.next().ok_or("Please provide a family of formulas to generate")?;
+    let mut output = None;
+    let mut num_vars = None;
+    let mut holes = None;
+    let mut k = 3;
+    let mut ratio = 4.26;
+    let mut seed = 0;
+    let mut satisfiable = true;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("Missing value for {}", name));
+        match arg.as_str() {
+            "-o" => output = Some(value("-o")?),
+            "--vars" => {
+                num_vars = Some(value("--vars")?.parse().map_err(|_| "Invalid value for --vars")?);
+            }
+            "--holes" => {
+                holes = Some(value("--holes")?.parse().map_err(|_| "Invalid value for --holes")?);
+            }
+            "--k" => k = value("--k")?.parse().map_err(|_| "Invalid value for --k")?,
+            "--ratio" => {
+                ratio = value("--ratio")?.parse().ok()
+                    .filter(|r: &f64| r.is_finite() && *r >= 0.0)
+                    .ok_or("Invalid value for --ratio")?;
+            }
+            "--seed" => seed = value("--seed")?.parse().map_err(|_| "Invalid value for --seed")?,
+            "--unsat" => satisfiable = false,
+            _ => return Err(format!("Unknown option '{}'", arg)),
+        }
+    }
 
+    let formula = match family.as_str() {
+        "random" => {
+            let num_vars = num_vars.ok_or("Please provide the number of variables with --vars")?;
+            if k == 0 || k > num_vars {
+                return Err("--k must be between 1 and the number of variables".to_owned());
+            }
+            generate::random_k_sat(num_vars, k, ratio, seed)
+        }
+        "pigeonhole" => {
+            generate::pigeonhole(holes.ok_or("Please provide the number of holes with --holes")?)
+        }
+        "parity" => match num_vars {
+            Some(num_vars) if num_vars > 0 => generate::parity(num_vars, seed, satisfiable),
+            _ => return Err("Please provide a positive number of variables with --vars".to_owned()),
+        },
+        _ => return Err(format!("Unknown family '{}'", family)),
+    };
+    match output {
+        Some(output) => write_file(&output, |out| export::write_dimacs(&formula, out)),
+        None => {
+            let mut out = BufWriter::new(io::stdout().lock());
+            export::write_dimacs(&formula, &mut out)
+                .and_then(|_| out.flush())
+                .map_err(|_| "Failed to write the formula".to_owned())
+        }
+    }
+}
+
+// Runs `cube <input> --depth <depth> -o <output>`, which writes the formula along with
+// the cubes found by lookahead as an iCNF file
+fn cube(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut file_name = None;
+    let mut depth = None;
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => {
+                let value: usize = args.next()
+                    .ok_or("Missing value for --depth")?
+                    .parse()
+                    .map_err(|_| "Invalid value for --depth")?;
+                depth = Some(value);
+            }
+            "-o" => output = Some(args.next().ok_or("Missing output file for -o")?),
+            _ => file_name = Some(arg),
+        }
+    }
+
+    let formula = open_formula(&file_name.ok_or("Please provide an input file")?,
+        ParseOptions::default())?;
+    let depth = depth.ok_or("Please provide a cube depth with --depth")?;
+    let output = output.ok_or("Please provide an output file with -o")?;
+
+    let cubes = formula.lookahead_cubes(depth);
+    write_file(&output, |out| export::write_icnf(&formula, &cubes, out))
+}
+
+// Runs `propagate <input> -a <lit>...`, which prints the DIMACS literals implied by unit
+// propagation under the assumed literals, or CONFLICT if propagation falsifies a clause
+fn propagate(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut file_name = None;
+    let mut assumptions = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-a" => {
+                let value: isize = args.next()
+                    .ok_or("Missing value for -a")?
+                    .parse()
+                    .map_err(|_| "Invalid value for -a")?;
+                assumptions.push(value);
+            }
+            _ => file_name = Some(arg),
+        }
+    }
+
+    let formula = open_formula(&file_name.ok_or("Please provide an input file")?,
+        ParseOptions::default())?;
+    check_literals(&formula, &assumptions)?;
+
+    let assumptions: Vec<_> = assumptions.into_iter().map(Literal::from_var).collect();
+    match formula.propagate_under(&assumptions) {
+        Ok(implied) => {
+            for lit in implied {
+                print!("{} ", lit.to_var());
+            }
+            println!("0");
+        }
+        Err(_) => println!("CONFLICT"),
+    }
+    Ok(())
+}
+
+// Runs `check-equiv <first> <second>`, which prints whether the formulas are logically
+// equivalent, and if not, an assignment satisfying only one of them and whether they
+// are equisatisfiable
+fn check_equiv(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let first_file = args.next().ok_or("Please provide two input files")?;
+    let second_file = args.next().ok_or("Please provide a second input file")?;
+    let first = open_formula(&first_file, ParseOptions::default())?;
+    let second = open_formula(&second_file, ParseOptions::default())?;
+
+    let mut heuristic = Heuristic::Ordered.build();
+    let (only, model) = match first.compare(&second, heuristic.as_mut()) {
+        Comparison::Equivalent => {
+            println!("EQUIVALENT");
+            return Ok(());
+        }
+        Comparison::FirstOnly(model) => (first_file, model),
+        Comparison::SecondOnly(model) => (second_file, model),
+        Comparison::Unknown => {
+            println!("UNKNOWN");
+            return Ok(());
+        }
+    };
+    println!("NOT EQUIVALENT");
+    println!("Satisfies only {}: {}", only, model);
+    match first.equisatisfiable(&second, heuristic.as_mut()) {
+        Some(true) => println!("EQUISATISFIABLE"),
+        Some(false) => println!("NOT EQUISATISFIABLE"),
+        None => println!("UNKNOWN"),
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+// Prints the counts of the preprocessing steps which ran
+fn print_preprocess_stats(stats: &PreprocessStats) {
+    if let Some(breaking) = stats.symmetry_breaking {
+        println!("Symmetry-breaking clauses: {}", breaking);
+    }
+    if let Some((subsumed, strengthened)) = stats.subsumption {
+        println!("Subsumed clauses: {}", subsumed);
+        println!("Removed literals: {}", strengthened);
+    }
+    if let Some(simplified) = &stats.binary {
+        println!("Hyper-binary resolvents: {}", simplified.hyper_binary);
+        println!("Substituted variables: {}", simplified.substituted);
+        println!("Reduced binary clauses: {}", simplified.reduced);
+    }
+    if let Some(aux_vars) = stats.aux_vars {
+        println!("Auxiliary variables: {}", aux_vars);
+    }
+    println!("Reclaimed clause memory: {} bytes", stats.reclaimed_bytes);
+}
+
+// Prints the time spent in each phase of the run, for --stats
+fn print_phase_times(phase_times: &[(&str, Duration)]) {
+    for (phase, time) in phase_times {
+        println!("{} time: {:?}", phase, time);
+    }
+}
+
+// Fails if any of the DIMACS literals is not a literal of the formula
+fn check_literals(formula: &Formula, vars: &[isize]) -> Result<(), String> {
+    match vars.iter().find(|v| **v == 0 || v.unsigned_abs() > formula.num_vars()) {
+        Some(var) => Err(format!("Literal {} is out of range", var)),
+        None => Ok(()),
+    }
+}
+
+// The clauses of the formula as lists of DIMACS literals
+fn dimacs_clauses(formula: &Formula) -> Vec<Vec<isize>> {
+    formula.clauses()
+        .map(|c| c.iter().map(|l| l.to_var()).collect())
+        .collect()
+}
+
+// Fails if the model falsifies any of the input clauses, for --verify
+fn check_model(input_clauses: &[Vec<isize>], model: &Assignment) -> Result<(), String> {
+    match eval::first_falsified(input_clauses, &model.completed_values()) {
+        Some(i) => Err(format!("Internal error: the model falsifies input clause {}", i + 1)),
+        None => Ok(()),
+    }
+}
+
+// Re-solves the formula several times with randomly perturbed clause order and
+// tie-breaking, and panics if any definite answer differs from another or if
+// any model fails to satisfy the input clauses. The seeds follow the base seed, which
+// is taken from the clock if not given
+fn check_chaos(formula: &Formula, input_clauses: &[Vec<isize>], heuristic: Heuristic,
+    base_seed: Option<u64>) {
+    // Returns whether the formula is satisfiable, or None if the solver gave up
+    let solve = |formula: Formula, seed| match formula.solve(heuristic.build().as_mut()) {
+        SolveResult::Sat(model) => {
+            let falsified = eval::first_falsified(input_clauses, &model.completed_values());
+            assert_eq!(falsified, None, "Chaos run with seed {:?} found an invalid model", seed);
+            Some(true)
+        }
+        SolveResult::Unsat => Some(false),
+        SolveResult::Unknown => None,
+    };
+
+    let mut expected = solve(formula.clone(), None);
+    let base_seed = base_seed.unwrap_or_else(|| {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64)
+    });
+
+    for seed in (0..CHAOS_RUNS).map(|i| base_seed.wrapping_add(i)) {
+        let mut perturbed = formula.clone();
+        perturbed.set_chaos_seed(Some(seed));
+        let answer = solve(perturbed, Some(seed));
+        if expected.is_some() && answer.is_some() {
+            assert_eq!(answer, expected, "Chaos run with seed {} changed the answer", seed);
+        }
+        expected = expected.or(answer);
+    }
+}
+
+// Prints the model count in the output format of the model counting competition
+fn print_count(problem_type: &str, models: u128) {
+    println!("s {}", if models == 0 { "UNSATISFIABLE" } else { "SATISFIABLE" });
+    println!("c s type {}", problem_type);
+    println!("c s log10-estimate {}", (models as f64).log10());
+    println!("c s exact arb int {}", models);
+}
+
+// Prints UNSATISFIABLE followed by the clauses of the core, or SATISFIABLE if
+// there is none
+fn print_core(core: CoreResult<Formula>) -> Result<(), String> {
+    match core {
+        CoreResult::Minimal(core) => {
+            println!("UNSATISFIABLE");
+            let stdout = io::stdout();
+            export::write_dimacs(&core, &mut stdout.lock())
+                .map_err(|_| "Failed to write clauses".to_owned())
+        }
+        CoreResult::Sat => {
+            println!("SATISFIABLE");
+            Ok(())
+        }
+        CoreResult::Unknown => {
+            println!("UNKNOWN");
+            Ok(())
+        }
+    }
+}
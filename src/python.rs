@@ -0,0 +1,79 @@
+// Python bindings through PyO3. Build the extension module with
+// `cargo rustc --release --lib --features python --crate-type cdylib` and import the
+// result as simple_sat_solver, or let maturin do both
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use crate::heuristic::Heuristic;
+use crate::solver::{Formula, Literal, SolveResult, Solver};
+
+/// An incremental solver for clauses of DIMACS literals. Variables are created as the
+/// literals mention them
+#[pyclass(name = "Solver", unsendable)]
+struct PySolver {
+    solver: Solver,
+}
+
+impl PySolver {
+    // Converts DIMACS literals, adding variables until they exist
+    fn literals(&mut self, literals: &[i32]) -> PyResult<Vec<Literal>> {
+        literals.iter().map(|&lit| {
+            if lit == 0 {
+                return Err(PyValueError::new_err("0 is not a DIMACS literal"));
+            }
+            let lit = Literal::from(lit);
+            while self.solver.formula().num_vars() <= lit.id() {
+                self.solver.new_var();
+            }
+            Ok(lit)
+        }).collect()
+    }
+}
+
+#[pymethods]
+impl PySolver {
+    #[new]
+    fn new() -> Self {
+        PySolver { solver: Solver::new(Formula::new(), Heuristic::Ordered.build()) }
+    }
+
+    /// Adds a clause given as a list of DIMACS literals
+    fn add_clause(&mut self, clause: Vec<i32>) -> PyResult<()> {
+        let clause = self.literals(&clause)?;
+        self.solver.add_clause(clause);
+        Ok(())
+    }
+
+    /// Makes later solves give up after the given number of conflicts, or never if None
+    fn set_conflict_limit(&mut self, limit: Option<u64>) {
+        self.solver.set_conflict_limit(limit);
+    }
+
+    /// Solves the clauses with the assumed DIMACS literals forced to be true. Returns
+    /// True if satisfiable, False if unsatisfiable and None if the search gave up
+    #[pyo3(signature = (assumptions = vec![]))]
+    fn solve(&mut self, assumptions: Vec<i32>) -> PyResult<Option<bool>> {
+        let assumptions = self.literals(&assumptions)?;
+        Ok(match self.solver.solve_under(&assumptions) {
+            SolveResult::Sat(_) => Some(true),
+            SolveResult::Unsat => Some(false),
+            SolveResult::Unknown => None,
+        })
+    }
+
+    /// The model found by the last solve as a list with the DIMACS literal of every
+    /// variable, which is 0 for variables either value works for, or None if there is none
+    fn model(&self) -> Option<Vec<i32>> {
+        self.solver.model().map(|model| model.dimacs_literals())
+    }
+
+    /// A minimal list of the assumptions of the last solve which make it unsatisfiable
+    fn failed(&self) -> Vec<i32> {
+        self.solver.failed().iter().map(|&lit| lit.into()).collect()
+    }
+}
+
+#[pymodule]
+fn simple_sat_solver(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PySolver>()
+}
@@ -1,153 +1,791 @@
+// The submodule named core shadows the core crate here, hence the leading ::
+use ::core::fmt;
+use ::core::ops::Not;
+use ::core::cell::RefCell;
+use ::core::time::Duration;
+use ::core::sync::atomic::{AtomicBool, Ordering};
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::fs::File;
-use std::fmt;
-use std::ops::Not;
-use std::io::{Read, Cursor, BufRead};
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::time::Instant;
+use crate::config::SolverConfig;
+use crate::heuristic::{DecisionHeuristic, Phase};
+use crate::rng::{self, Rng};
+use self::clause_db::ClauseDb;
+use self::occurrences::Occurrences;
+
+mod backbone;
+mod binary;
+mod cardinality;
+mod clause_db;
+mod clause_index;
+mod core;
+mod count;
+mod dimacs;
+mod enumerate;
+mod equivalence;
+mod horn;
+mod incremental;
+mod log;
+mod lookahead;
+mod maxsat;
+mod occurrences;
+mod partial;
+mod polish;
+#[cfg(feature = "std")]
+mod portfolio;
+mod preprocess;
+mod propagator;
+mod pseudo_boolean;
+#[cfg(feature = "serde")]
+mod serialize;
+mod symmetry;
+#[cfg(test)]
+mod testing;
+mod trace;
+mod two_sat;
+mod var_map;
+mod walk_sat;
+mod xor;
+
+pub use self::binary::BinaryStats;
+pub use self::cardinality::{CardinalityConstraints, CardinalityEncoding};
+pub use self::core::CoreResult;
+pub use self::dimacs::{CountingProblem, ParseOptions};
+pub use self::enumerate::Models;
+pub use self::equivalence::Comparison;
+pub use self::incremental::Solver;
+pub use self::log::{LogCallback, LogLevel};
+pub use self::maxsat::{MaxSatAlgorithm, MaxSatResult, WeightedFormula};
+pub use self::preprocess::PreprocessStats;
+pub use self::propagator::ExternalPropagator;
+pub use self::pseudo_boolean::PbProblem;
+pub use self::trace::{Trace, TraceResult};
+pub use self::var_map::VarMap;
+pub use self::xor::XorConstraints;
+
+/// The outcome of solving a formula
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SolveResult {
+    /// The formula is satisfied by the assignment
+    Sat(Assignment),
+    Unsat,
+    /// The solver gave up without an answer
+    Unknown,
+}
+
+impl From<Option<Assignment>> for SolveResult {
+    fn from(model: Option<Assignment>) -> Self {
+        model.map_or(SolveResult::Unsat, SolveResult::Sat)
+    }
+}
+
+/// Counters describing the work done by a search. The search is DPLL without clause
+/// learning or restarts, so there are no counters for learned or deleted clauses or
+/// for restarts
+#[derive(Copy, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stats {
+    /// Literals branched on, counting each try of the flipped literal after
+    /// backtracking as well
+    pub decisions: u64,
+    /// Times the search found a falsified clause
+    pub conflicts: u64,
+    /// Clauses visited to propagate assignments, i.e. the clauses containing each
+    /// assigned literal or its negation, whose counters of true and open literals were
+    /// updated. Every assignment is a decision, so this is the work they cause
+    pub propagations: u64,
+    /// Clauses left out before the search because they repeat an earlier clause or are
+    /// tautologies, when parsing with `ParseOptions::dedup` or adding them to a `Solver`
+    pub duplicate_clauses: u64,
+    /// Peak resident memory of the process in kilobytes, where the platform reports it
+    pub peak_memory_kb: Option<u64>,
+}
+
+/// Receives the counters of a running search, see `Formula::set_progress`
+pub type ProgressCallback = Arc<dyn Fn(&Stats) + Send + Sync>;
+
+/// Unit propagation falsified a clause, or the assumptions contradict each other
+#[derive(Debug)]
+pub struct Conflict;
+
+/// The algorithm used to search for a model
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Engine {
+    /// Complete backtracking search
+    Dpll,
+    /// WalkSAT local search from a random assignment seeded by `seed`. It can find models
+    /// but not prove unsatisfiability, and gives up with Unknown after `max_flips` flips
+    WalkSat { max_flips: u64, seed: u64 },
+}
 
 /// A set of clauses
+#[derive(Clone)]
 pub struct Formula {
-    clauses: Vec<Clause>,
+    clauses: ClauseDb,
     assignment: Assignment,
-    next_literal_id: usize,
+    // Variables from this id on were introduced by preprocessing and are left out of models
+    num_input_vars: usize,
+    phase: Phase,
+    // Polarities to try variables in first, overriding the heuristic and the phase
+    hint: Option<Assignment>,
+    // Variables the simplifications must keep, see `freeze`
+    frozen: BTreeSet<usize>,
+    engine: Engine,
+    // Positive minus negative occurrences of each input variable
+    occurrence_balance: Vec<isize>,
+    // Perturbs clause order and tie-breaking when set
+    chaos: RefCell<Option<Rng>>,
+    // Counters for the search
+    stats: Stats,
+    budget: Budget,
+    // Called with the counters every so many decisions
+    progress: Option<(u64, ProgressCallback)>,
+    // Called with a line for each event of the search up to the level
+    log: Option<(LogLevel, LogCallback)>,
+    // The clauses of each literal, tracked during the search
+    occurrences: Option<Occurrences>,
+}
+
+// The propagations between reads of the clock, on top of the reads every few decisions
+const PROPAGATIONS_PER_CLOCK_READ: u64 = 1 << 16;
+
+// Limits after which the search gives up with Unknown
+#[derive(Clone, Default)]
+struct Budget {
+    conflicts: Option<u64>,
+    propagations: Option<u64>,
+    time: Option<Duration>,
+    // Set from elsewhere to stop the search
+    interrupt: Option<Arc<AtomicBool>>,
+    // When the time runs out, set once the search starts
+    deadline: Option<Instant>,
+    exhausted: bool,
+}
+
+impl Budget {
+    // Starts the clock, unless a search sharing the budget already did
+    fn start(&mut self) {
+        if self.deadline.is_none() {
+            self.deadline = self.time.and_then(deadline);
+        }
+    }
+
+    // Takes the work of a search off the limits, leaving the rest to the next search
+    // sharing the budget
+    fn spend(&mut self, stats: &Stats) {
+        self.conflicts = self.conflicts.map(|c| c.saturating_sub(stats.conflicts));
+        self.propagations = self.propagations.map(|p| p.saturating_sub(stats.propagations));
+    }
+}
+
+impl Default for Formula {
+    fn default() -> Self {
+        Formula::new()
+    }
 }
 
 impl Formula {
-    /// Parses a DIMACS file and returns the corresponding formula or an error
-    pub fn parse_dimacs(mut file: File) -> Result<Formula, String> {
-        let mut buf = String::new();
-        file.read_to_string(&mut buf).map_err(|_| "Error while reading file")?;
-        let mut buf = Cursor::new(buf);
-
-        // Parse comments and problem line
-        let problem_line: String = (&mut buf).lines()
-            .map(Result::unwrap)
-            .find(|l| !l.starts_with('c'))
-            .ok_or("Missing problem line")?;
-
-        let num_vars: usize;
-        let num_clauses: usize;
-        let params: Vec<_> = problem_line.split_whitespace().collect();
-        if params.len() != 4 || params[0] != "p" {
-            return Err("Invalid/Missing problem line".to_owned());
-        } else if params[1] != "cnf" {
-            return Err("Only cnf-formatted inputs are currently supported".to_owned());
-        } else {
-            num_vars = params[2].parse()
-                .map_err(|_| "Third problem line parameter invalid".to_owned())?;
-            num_clauses = params[3].parse()
-                .map_err(|_| "Fourth problem line parameter invalid".to_owned())?;
+    /// Creates a formula without variables or clauses, to be built with `new_var` and
+    /// `add_clause`
+    pub fn new() -> Formula {
+        Formula {
+            clauses: ClauseDb::new(),
+            assignment: Assignment::new(0),
+            num_input_vars: 0,
+            phase: Phase::Positive,
+            hint: None,
+            frozen: BTreeSet::new(),
+            engine: Engine::Dpll,
+            occurrence_balance: vec![],
+            chaos: RefCell::new(None),
+            stats: Stats::default(),
+            budget: Budget::default(),
+            progress: None,
+            log: None,
+            occurrences: None,
         }
+    }
+
+    /// Adds a fresh variable to the formula
+    pub fn new_var(&mut self) -> Var {
+        let var = Var(self.num_vars());
+        self.assignment.0.push(None);
+        self.num_input_vars += 1;
+        self.occurrence_balance.push(0);
+        var
+    }
+
+    /// Adds a clause over variables of the formula. Panics if some literal is over
+    /// a variable that doesn't belong to the formula
+    pub fn add_clause(&mut self, literals: impl IntoIterator<Item = Literal>) {
+        let num_vars = self.num_vars();
+        let balance = &mut self.occurrence_balance;
+        self.clauses.push(literals.into_iter().inspect(|lit| {
+            assert!(lit.id() < num_vars, "Literal over unknown variable {}", lit.to_var());
+            balance[lit.id()] += if lit.is_negated() { -1 } else { 1 };
+        }));
+        self.track_last_clause();
+    }
+
+    /// Marks the variable as frozen, so simplifications such as `preprocess` neither
+    /// replace it nor change which values it can take, and it can still be used in
+    /// clauses and assumptions added later. Passes which only hold for the clauses so
+    /// far, like symmetry breaking, leave it alone
+    pub fn freeze(&mut self, var: Var) {
+        self.frozen.insert(var.0);
+    }
+
+    /// Undoes `freeze`, allowing simplifications to replace the variable again
+    pub fn melt(&mut self, var: Var) {
+        self.frozen.remove(&var.0);
+    }
+
+    /// Whether the variable is frozen, see `freeze`
+    pub fn is_frozen(&self, var: Var) -> bool {
+        self.frozen.contains(&var.0)
+    }
+
+    /// Parses a DIMACS file and returns the corresponding formula or an error
+    #[cfg(feature = "std")]
+    pub fn parse_dimacs(file: File) -> Result<Formula, String> {
+        Formula::parse_dimacs_with(file, ParseOptions::default())
+    }
 
-        // Parse the variables
-        let mut formula = Formula {
-            clauses: vec![Clause::new(); num_clauses],
+    /// Parses a DIMACS file with the given options, see `parse_dimacs_str_with`
+    #[cfg(feature = "std")]
+    pub fn parse_dimacs_with(mut file: File, options: ParseOptions) -> Result<Formula, String> {
+        let mut buf = vec![];
+        file.read_to_end(&mut buf).map_err(|_| "Error while reading file")?;
+        let (num_vars, clauses, duplicates) = Formula::parse_dimacs_clauses(&buf, options)?;
+        Ok(Formula::from_clauses(num_vars, clauses, duplicates))
+    }
+
+    /// Parses DIMACS text and returns the corresponding formula or an error
+    pub fn parse_dimacs_str(input: &str) -> Result<Formula, String> {
+        Formula::parse_dimacs_str_with(input, ParseOptions::default())
+    }
+
+    /// Parses DIMACS text with the given options. By default a variable above the
+    /// number declared by the problem line is an error
+    pub fn parse_dimacs_str_with(input: &str, options: ParseOptions) -> Result<Formula, String> {
+        let (num_vars, clauses, duplicates) =
+            Formula::parse_dimacs_clauses(input.as_bytes(), options)?;
+        Ok(Formula::from_clauses(num_vars, clauses, duplicates))
+    }
+
+    // Creates a formula over the given number of variables with the clauses, which
+    // must only contain those variables, counting the duplicates left out of them
+    fn from_clauses(num_vars: usize, clauses: ClauseDb, duplicates: u64) -> Formula {
+        let mut occurrence_balance = vec![0; num_vars];
+        for lit in clauses.iter().flatten() {
+            occurrence_balance[lit.id()] += if lit.is_negated() { -1 } else { 1 };
+        }
+        Formula {
+            clauses,
             assignment: Assignment::new(num_vars),
-            next_literal_id: 0,
-        };
+            num_input_vars: num_vars,
+            occurrence_balance,
+            stats: Stats { duplicate_clauses: duplicates, ..Stats::default() },
+            ..Formula::new()
+        }
+    }
 
-        let pos = buf.position() as usize;
-        let clauses = &buf.into_inner()[pos..];
-        let mut clause_str_iter = clauses.trim_end().split(" 0");
+    /// A hash of the formula which doesn't depend on the order of clauses or of the
+    /// literals within them, and which is stable across runs and platforms
+    pub fn fingerprint(&self) -> u64 {
+        self.clauses.iter()
+            .map(|c| clause_index::hash(&c.normalized()))
+            .fold(rng::mix(self.num_vars() as u64), u64::wrapping_add)
+    }
 
-        for (clause, clause_str) in formula.clauses.iter_mut().zip(&mut clause_str_iter) {
-            for v in clause_str.split_whitespace() {
-                let v: isize = v.parse().map_err(|_| format!("Illegal variable '{}'", v))?;
-                let lit = Literal::from_var(v);
-                clause.0.push(lit);
-            }
+    // Creates a formula over the same variables with the given clauses instead,
+    // which is solved with the complete engine
+    fn with_clauses(&self, clauses: ClauseDb) -> Formula {
+        Formula {
+            clauses,
+            assignment: Assignment::new(self.num_vars()),
+            num_input_vars: self.num_input_vars,
+            phase: self.phase,
+            hint: self.hint.clone(),
+            frozen: self.frozen.clone(),
+            engine: Engine::Dpll,
+            occurrence_balance: self.occurrence_balance.clone(),
+            chaos: RefCell::new(None),
+            stats: Stats::default(),
+            budget: Budget::default(),
+            progress: None,
+            log: None,
+            occurrences: None,
         }
+    }
 
-        match clause_str_iter.next() {
-            Some("") => Ok(formula),
-            None => Err("Not enough clauses".to_owned()),
-            _ => Err("Too many clauses".to_owned()),
+    /// Makes DPLL give up with Unknown after the given number of conflicts
+    pub fn set_conflict_limit(&mut self, limit: Option<u64>) {
+        self.budget.conflicts = limit;
+    }
+
+    /// Makes DPLL give up with Unknown once it has made the given number of propagations,
+    /// see `Stats::propagations`. Unlike conflicts, propagations grow with the size of
+    /// the formula, so this bounds the work even where each decision takes long
+    pub fn set_propagation_limit(&mut self, limit: Option<u64>) {
+        self.budget.propagations = limit;
+    }
+
+    /// Makes the search give up with Unknown once it has run for the given time. Ignored
+    /// without the `std` feature and on wasm32-unknown-unknown, which have no clock
+    pub fn set_time_limit(&mut self, limit: Option<Duration>) {
+        self.budget.time = limit;
+    }
+
+    /// Makes the search give up with Unknown soon after the flag gets set, e.g. from
+    /// another thread or a signal handler
+    pub fn set_interrupt(&mut self, interrupt: Option<Arc<AtomicBool>>) {
+        self.budget.interrupt = interrupt;
+    }
+
+    /// Makes DPLL call the callback with the counters so far every `interval` decisions,
+    /// e.g. to display progress. The interval must be positive
+    pub fn set_progress(&mut self, progress: Option<(u64, ProgressCallback)>) {
+        self.progress = progress;
+    }
+
+    /// Makes the solver randomly perturb choices that must not affect the answer,
+    /// such as clause order and heuristic tie-breaking, based on the given seed.
+    /// Used to flush out order-dependent bugs
+    pub fn set_chaos_seed(&mut self, seed: Option<u64>) {
+        *self.chaos.get_mut() = seed.map(Rng::new);
+    }
+
+    /// Sets the algorithm used by `solve`
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+    }
+
+    /// Sets the phase variables are tried in first by heuristics which don't pick polarities
+    pub fn set_phase(&mut self, phase: Phase) {
+        self.phase = phase;
+    }
+
+    /// Applies the phase, engine, chaos seed and limits of the configuration. The
+    /// heuristic is passed to `solve`, and the simplifications are run by `preprocess`
+    pub fn configure(&mut self, config: &SolverConfig) {
+        self.set_phase(config.phase);
+        self.set_engine(config.engine);
+        self.set_chaos_seed(config.chaos_seed);
+        self.set_conflict_limit(config.conflict_limit);
+        self.set_propagation_limit(config.propagation_limit);
+        self.set_time_limit(config.time_limit);
+    }
+
+    /// Warm-starts the search from a full or partial assignment, such as a model of a
+    /// similar formula: variables it assigns are tried in its polarity first, whatever
+    /// the heuristic and the phase would pick, and local search starts from it
+    pub fn set_hint(&mut self, hint: Option<Assignment>) {
+        self.hint = hint;
+    }
+
+    /// Returns the literal of the variable with the given id in its preferred phase,
+    /// which is that of the hint if it assigns the variable
+    pub fn preferred_literal(&self, id: usize) -> Literal {
+        if let Some(negated) = self.hinted(id) {
+            return Literal::new(id, negated);
         }
+        let negated = match self.phase {
+            Phase::Positive => false,
+            Phase::Negative => true,
+            Phase::Occurrence => self.occurrence_balance.get(id).is_some_and(|&b| b < 0),
+        };
+        Literal::new(id, negated)
+    }
+
+    // Whether the hint has the variable negated, if it assigns it
+    fn hinted(&self, id: usize) -> Option<bool> {
+        self.hint.as_ref().and_then(|hint| hint.0.get(id).copied().flatten())
+    }
+
+    // The literal to branch on first for the heuristic's pick, flipped to the hint
+    fn decision(&self, lit: Literal) -> Literal {
+        self.hinted(lit.id()).map_or(lit, |negated| Literal::new(lit.id(), negated))
+    }
+
+    /// The number of variables in the formula
+    pub fn num_vars(&self) -> usize {
+        self.assignment.0.len()
     }
 
-    fn solved(&self) -> bool {
-        self.clauses.iter().all(|c| c.solved(&self.assignment))
+    /// Iterates over the literals of each clause
+    pub fn clauses(&self) -> impl Iterator<Item = &[Literal]> {
+        self.clauses.iter()
     }
 
-    fn unsolvable(&self) -> bool {
-        self.clauses.iter().any(|c| c.unsolvable(&self.assignment))
+    /// Returns whether the literal is currently true, false or unassigned (None)
+    pub fn value(&self, lit: Literal) -> Option<bool> {
+        self.assignment.0[lit.id()].map(|negated| negated == lit.is_negated())
     }
 
-    fn next_un_assigned(&self) -> Literal {
-        let id = (self.next_literal_id..)
-            .find(|&i| self.assignment.0[i].is_none()).unwrap();
-        Literal {
-            id,
-            negated: false,
+    /// Returns the unassigned literal with the highest score. Ties are broken
+    /// randomly in chaos mode, and otherwise by preferring lower ids and positive literals.
+    /// Panics if every variable is assigned
+    pub fn best_un_assigned(&self, score: impl Fn(Literal) -> f64) -> Literal {
+        let mut chaos = self.chaos.borrow_mut();
+        let mut best = None;
+        let mut best_score = f64::NEG_INFINITY;
+        let mut ties = 0;
+        for id in (0..self.num_vars()).filter(|&i| self.assignment.0[i].is_none()) {
+            for &negated in &[false, true] {
+                let lit = Literal::new(id, negated);
+                let lit_score = score(lit);
+                if lit_score > best_score {
+                    best = Some(lit);
+                    best_score = lit_score;
+                    ties = 1;
+                } else if lit_score == best_score {
+                    ties += 1;
+                    if let Some(rng) = chaos.as_mut() {
+                        if rng.below(ties) == 0 {
+                            best = Some(lit);
+                        }
+                    }
+                }
+            }
         }
+        best.unwrap()
     }
 
-    fn assign(&mut self, lit: Literal) {
-        self.next_literal_id = lit.id + 1;
-        self.assignment.assign(lit);
+    fn assign(&mut self, lit: Literal, heuristic: &mut dyn DecisionHeuristic) {
+        self.set_literal(lit);
+        heuristic.on_assign(lit);
     }
 
-    fn un_assign(&mut self, lit: Literal) {
-        self.next_literal_id = lit.id;
-        self.assignment.un_assign(lit);
+    fn un_assign(&mut self, lit: Literal, heuristic: &mut dyn DecisionHeuristic) {
+        self.unset_literal(lit);
+        heuristic.on_un_assign(lit);
     }
 
-    /// Solves the formula and returns an Assignment or None if it isn't possible
-    pub fn solve(mut self) -> Option<Assignment> {
-        if self.dpll() {
-            Some(self.assignment)
+    /// Solves the formula with the configured engine. DPLL branches according to the
+    /// given heuristic, except on 2-SAT and (renamable) Horn formulas, which are solved
+    /// in linear time without branching. A formula without clauses is satisfied right
+    /// away, leaving every variable unassigned, and one with the empty clause is
+    /// unsatisfiable
+    pub fn solve(self, heuristic: &mut dyn DecisionHeuristic) -> SolveResult {
+        self.solve_with_stats(heuristic).0
+    }
+
+    /// Like `solve`, but also returns counters for the work done
+    pub fn solve_with_stats(mut self, heuristic: &mut dyn DecisionHeuristic)
+        -> (SolveResult, Stats) {
+        if let Some(rng) = self.chaos.get_mut() {
+            self.clauses.shuffle(rng);
+        }
+
+        let num_input_vars = self.num_input_vars;
+        self.budget.start();
+        // Without clauses every assignment is a model, while the empty clause has none,
+        // so neither needs a search
+        let mut result = if self.clauses.len() == 0 {
+            SolveResult::Sat(Assignment::new(num_input_vars))
+        } else if self.clauses.iter().any(|c| c.is_empty()) {
+            SolveResult::Unsat
+        } else if let Engine::WalkSat { max_flips, seed } = self.engine {
+            self.log_start("WalkSAT");
+            self.walk_sat(max_flips, seed)
+        } else if self.is_two_sat() {
+            self.log_start("2-SAT");
+            self.solve_two_sat().into()
+        } else if let Some(flips) = self.horn_renaming() {
+            self.log_start("renamable Horn-SAT");
+            self.solve_horn(&flips).into()
         } else {
-            None
+            self.log_start("DPLL");
+            heuristic.init(&self);
+            self.track_occurrences();
+            if self.dpll(heuristic) {
+                SolveResult::Sat(::core::mem::replace(&mut self.assignment, Assignment::new(0)))
+            } else if self.budget.exhausted {
+                SolveResult::Unknown
+            } else {
+                SolveResult::Unsat
+            }
+        };
+        if let SolveResult::Sat(assignment) = &mut result {
+            assignment.0.truncate(num_input_vars);
+        }
+        self.log_result(&result);
+        self.stats.peak_memory_kb = peak_memory_kb();
+        (result, self.stats)
+    }
+
+    // Solves the formula as one of several searches which share the budget, e.g. the
+    // steps of a minimization, so that together they keep to the limits
+    fn solve_sharing(mut self, budget: &mut Budget, heuristic: &mut dyn DecisionHeuristic)
+        -> (SolveResult, Stats) {
+        budget.start();
+        self.budget = budget.clone();
+        let (result, stats) = self.solve_with_stats(heuristic);
+        budget.spend(&stats);
+        (result, stats)
+    }
+
+    // Whether the search has used up its budget, in which case every branch still
+    // open fails right away. The clock is only read every few decisions
+    fn out_of_budget(&mut self) -> bool {
+        let (budget, stats) = (&mut self.budget, &self.stats);
+        if !budget.exhausted {
+            budget.exhausted = budget.conflicts.is_some_and(|c| stats.conflicts >= c)
+                || interrupted(&budget.interrupt)
+                || stats.decisions % 256 == 0 && timed_out(budget.deadline);
         }
+        budget.exhausted
     }
 
-    fn dpll(&mut self) -> bool {
-        !self.unsolvable() && (self.solved() || {
-            let next = self.next_un_assigned();
-            self.assign(next);
-            self.dpll() || {
-                self.un_assign(next);
-                self.assign(!next);
-                let res = self.dpll();
-                if !res { self.un_assign(!next) }
+    // Counts the propagations of an assignment, after which the search is out of budget
+    // if the propagation limit is reached. A single decision can take long on a large
+    // formula, so the clock is also read every PROPAGATIONS_PER_CLOCK_READ propagations
+    fn count_propagations(&mut self, visited: u64) {
+        let before = self.stats.propagations;
+        self.stats.propagations += visited;
+        let (budget, propagations) = (&mut self.budget, self.stats.propagations);
+        if !budget.exhausted {
+            let clock_read = before / PROPAGATIONS_PER_CLOCK_READ
+                != propagations / PROPAGATIONS_PER_CLOCK_READ;
+            budget.exhausted = budget.propagations.is_some_and(|p| propagations >= p)
+                || clock_read && timed_out(budget.deadline);
+        }
+    }
+
+    // Counts the decision, which has just been assigned, reporting progress when it is due
+    fn count_decision(&mut self, lit: Literal) {
+        self.log_decision(lit);
+        self.stats.decisions += 1;
+        if let Some((interval, callback)) = &self.progress {
+            if self.stats.decisions.is_multiple_of(*interval) {
+                callback(&Stats { peak_memory_kb: peak_memory_kb(), ..self.stats });
+            }
+        }
+    }
+
+    fn dpll(&mut self, heuristic: &mut dyn DecisionHeuristic) -> bool {
+        if self.out_of_budget() {
+            return false;
+        }
+        if let Some(clause) = self.conflict() {
+            self.log_conflict(clause);
+            heuristic.on_conflict(clause);
+            self.stats.conflicts += 1;
+            return false;
+        }
+
+        self.solved() || {
+            let next = self.decision(heuristic.pick_branch_literal(self));
+            self.assign(next, heuristic);
+            self.count_decision(next);
+            self.dpll(heuristic) || {
+                self.un_assign(next, heuristic);
+                self.assign(!next, heuristic);
+                self.count_decision(!next);
+                let res = self.dpll(heuristic);
+                if !res { self.un_assign(!next, heuristic) }
                 res
             }
-        })
+        }
     }
 }
 
-/// A disjunction of literals
-#[derive(Clone)]
-struct Clause(Vec<Literal>);
+// Without the standard library there is no clock, so there are no deadlines either
+#[cfg(not(feature = "std"))]
+#[derive(Copy, Clone)]
+enum Instant {}
 
-impl Clause {
-    fn new() -> Self {
-        Clause(vec![])
+// The instant the time budget runs out. There is no clock on wasm32-unknown-unknown,
+// where Instant::now panics, so time limits are ignored there
+#[cfg(feature = "std")]
+fn deadline(time: Duration) -> Option<Instant> {
+    if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+        None
+    } else {
+        Some(Instant::now() + time)
     }
+}
 
+#[cfg(not(feature = "std"))]
+fn deadline(_time: Duration) -> Option<Instant> {
+    None
+}
+
+// Whether the deadline has passed
+#[cfg(feature = "std")]
+fn timed_out(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|d| Instant::now() >= d)
+}
+
+#[cfg(not(feature = "std"))]
+fn timed_out(deadline: Option<Instant>) -> bool {
+    deadline.is_some()
+}
+
+// Whether the interrupt flag has been set
+fn interrupted(interrupt: &Option<Arc<AtomicBool>>) -> bool {
+    interrupt.as_ref().is_some_and(|i| i.load(Ordering::Relaxed))
+}
+
+// The peak resident memory of the process as reported by Linux
+#[cfg(feature = "std")]
+fn peak_memory_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(not(feature = "std"))]
+fn peak_memory_kb() -> Option<u64> {
+    None
+}
+
+// A disjunction of literals, as stored in a ClauseDb
+trait Clause {
+    fn solved(&self, assignment: &Assignment) -> bool;
+
+    // The sorted, duplicate-free literals of the clause, which are equal for equivalent clauses
+    fn normalized(&self) -> Vec<Literal>;
+}
+
+impl Clause for [Literal] {
     fn solved(&self, assignment: &Assignment) -> bool {
-        self.0.iter().any(|l| assignment.assigned(*l))
+        self.iter().any(|l| assignment.assigned(*l))
     }
 
-    fn unsolvable(&self, assignment: &Assignment) -> bool {
-        self.0.iter().all(|l| assignment.assigned(!*l))
+    fn normalized(&self) -> Vec<Literal> {
+        let mut lits = self.to_vec();
+        lits.sort_unstable_by_key(|l| l.index());
+        lits.dedup();
+        lits
     }
 }
 
 /// A propositional variable (p, q, etc.) with some id which may be negated
 /// Ex.: p, !q
-#[derive(Copy, Clone, PartialEq)]
-struct Literal {
-    id: usize,
-    negated: bool,
-}
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Literal(u32);
 
 impl Literal {
-    // Creates a literal from a variable. A variable is e.g. 3 or -42,
-    // which would have ids of 2 and 41 respectively
-    fn from_var(var: isize) -> Self {
-        Literal {
-            id: var.abs() as usize - 1,
-            negated: var < 0,
-        }
+    /// The largest variable id a literal can have, as literals are packed into 32 bits
+    pub const MAX_ID: usize = (u32::MAX >> 1) as usize;
+
+    /// Creates a literal of the variable with the given 0-based id. Panics if the id exceeds
+    /// `MAX_ID`
+    pub fn new(id: usize, negated: bool) -> Self {
+        assert!(id <= Literal::MAX_ID, "Variable id {} is out of range", id);
+        Literal((id as u32) << 1 | negated as u32)
+    }
+
+    /// The 0-based id of the literal's variable
+    pub fn id(self) -> usize {
+        (self.0 >> 1) as usize
+    }
+
+    /// Whether the literal is the negation of its variable
+    pub fn is_negated(self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    /// The literal's variable
+    pub fn var(self) -> Var {
+        Var(self.id())
+    }
+
+    /// Creates a literal from a variable. A variable is e.g. 3 or -42,
+    /// which would have ids of 2 and 41 respectively
+    pub fn from_var(var: isize) -> Self {
+        Literal::new(var.unsigned_abs() - 1, var < 0)
+    }
+
+    /// The DIMACS variable of the literal, the inverse of `from_var`
+    pub fn to_var(self) -> isize {
+        let var = self.id() as isize + 1;
+        if self.is_negated() { -var } else { var }
+    }
+
+    /// The position of this literal in per-literal tables of size 2 * num_vars, which is
+    /// its packed form `2 * id + negated`
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// The literal at the position in per-literal tables, the inverse of `index`
+    pub fn from_index(index: usize) -> Self {
+        Literal::new(index / 2, index % 2 == 1)
+    }
+}
+
+/// A variable of a formula, as created by `Formula::new_var`
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Var(usize);
+
+impl Var {
+    /// The 0-based id of the variable
+    pub fn id(self) -> usize {
+        self.0
+    }
+
+    /// The literal which is true when the variable is
+    pub fn positive(self) -> Literal {
+        Literal::new(self.0, false)
+    }
+
+    /// The literal which is true when the variable is false
+    pub fn negative(self) -> Literal {
+        Literal::new(self.0, true)
+    }
+}
+
+impl From<i32> for Literal {
+    /// Converts from a DIMACS literal, see `from_var`
+    fn from(var: i32) -> Self {
+        Literal::from_var(var as isize)
+    }
+}
+
+impl From<Literal> for i32 {
+    /// Converts to a DIMACS literal, see `to_var`
+    fn from(lit: Literal) -> Self {
+        lit.to_var() as i32
+    }
+}
+
+impl fmt::Display for Literal {
+    /// Displays the literal in DIMACS form, e.g. -42
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_var())
+    }
+}
+
+impl From<i32> for Var {
+    /// Converts from a positive DIMACS variable, which is its id plus one
+    fn from(var: i32) -> Self {
+        assert!(var > 0, "DIMACS variables are positive, got {}", var);
+        Var(var as usize - 1)
+    }
+}
+
+impl From<Var> for i32 {
+    /// Converts to a DIMACS variable, which is the id plus one
+    fn from(var: Var) -> Self {
+        var.0 as i32 + 1
+    }
+}
+
+impl fmt::Display for Var {
+    /// Displays the variable in DIMACS form, e.g. 42
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0 + 1)
     }
 }
 
@@ -155,16 +793,16 @@ impl Not for Literal {
     type Output = Literal;
 
     fn not(self) -> Self::Output {
-        Literal {
-            id: self.id,
-            negated: !self.negated,
-        }
+        Literal(self.0 ^ 1)
     }
 }
 
 /// The assigned literals
 /// Each spot in the Vec is either a bool determining whether the assigned literal is negated
 /// or None, if neither literal with that id is assigned
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "Vec<Option<bool>>", from = "Vec<Option<bool>>"))]
 pub struct Assignment(Vec<Option<bool>>);
 
 impl Assignment {
@@ -173,27 +811,234 @@ impl Assignment {
     }
 
     fn assign(&mut self, lit: Literal) {
-        self.0[lit.id] = Some(lit.negated);
+        self.0[lit.id()] = Some(lit.is_negated());
     }
 
     fn un_assign(&mut self, lit: Literal) {
-        self.0[lit.id] = None;
+        self.0[lit.id()] = None;
     }
 
     fn assigned(&self, lit: Literal) -> bool {
-        self.0[lit.id] == Some(lit.negated)
+        self.0[lit.id()] == Some(lit.is_negated())
+    }
+
+    /// The value of the variable, or None if it is unassigned
+    pub fn value(&self, var: Var) -> Option<bool> {
+        self.0[var.0].map(|negated| !negated)
+    }
+
+    /// The assigned variables along with their values, ordered by variable
+    pub fn iter(&self) -> impl Iterator<Item = (Var, bool)> + '_ {
+        self.0.iter().enumerate()
+            .filter_map(|(id, negated)| negated.map(|n| (Var(id), !n)))
+    }
+
+    /// The number of variables the assignment covers, assigned or not
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the assignment covers no variables
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The value of every variable, with unassigned variables set to false
+    pub fn completed_values(&self) -> Vec<bool> {
+        self.0.iter().map(|&negated| negated == Some(false)).collect()
+    }
+
+    /// The assignment with every unassigned variable set to the given value, for tools
+    /// which expect a value for every variable of the model
+    pub fn completed(&self, value: bool) -> Assignment {
+        Assignment(self.0.iter().map(|negated| Some(negated.unwrap_or(!value))).collect())
+    }
+
+    /// The DIMACS literal of every variable, in order, with 0 for unassigned variables
+    pub fn dimacs_literals(&self) -> Vec<i32> {
+        self.0.iter().enumerate()
+            .map(|(id, negated)| negated.map_or(0, |n| Literal::new(id, n).into()))
+            .collect()
+    }
+
+    /// A hash of the assigned literals which doesn't depend on the order they were
+    /// assigned in, and which is stable across runs and platforms
+    pub fn fingerprint(&self) -> u64 {
+        self.0.iter().enumerate()
+            .filter_map(|(id, negated)| negated.map(|n| Literal::new(id, n)))
+            .fold(0, |hash, lit| hash.wrapping_add(rng::mix(lit.index() as u64)))
+    }
+}
+
+impl From<Vec<Option<bool>>> for Assignment {
+    /// Converts from the value of each variable, None if it is unassigned
+    fn from(values: Vec<Option<bool>>) -> Self {
+        Assignment(values.into_iter().map(|value| value.map(|v| !v)).collect())
+    }
+}
+
+impl From<Assignment> for Vec<Option<bool>> {
+    fn from(assignment: Assignment) -> Self {
+        assignment.0.into_iter().map(|negated| negated.map(|n| !n)).collect()
     }
 }
 
 impl fmt::Display for Assignment {
+    /// Writes the assigned literals as a DIMACS clause, leaving out the unassigned
+    /// variables, which can take either value
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for (id, negated) in self.0.iter().enumerate() {
-            match negated {
-                Some(n) => write!(f, "{}{} ", if *n { "-" } else { "" }, id)?,
-                None => write!(f, "{} UNASSIGNED", id)?,
+            if let Some(n) = negated {
+                write!(f, "{} ", Literal::new(id, *n))?;
             }
-
         }
         write!(f, "0")
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use crate::config::SolverConfig;
+    use crate::generate;
+    use crate::heuristic::Heuristic;
+    use super::{testing, Formula, Literal, ParseOptions, SolveResult, Solver};
+
+    #[test]
+    fn propagation_limit_gives_up() {
+        let mut heuristic = Heuristic::Ordered.build();
+        let (_, stats) = generate::pigeonhole(5).solve_with_stats(heuristic.as_mut());
+        let mut formula = generate::pigeonhole(5);
+        let limit = stats.propagations / 2;
+        formula.set_propagation_limit(Some(limit));
+        let (result, limited) = formula.solve_with_stats(Heuristic::Ordered.build().as_mut());
+        assert!(matches!(result, SolveResult::Unknown));
+        assert!(limited.propagations >= limit && limited.propagations < stats.propagations);
+
+        let mut formula = generate::pigeonhole(5);
+        formula.set_propagation_limit(Some(stats.propagations + 1));
+        let result = formula.solve(Heuristic::Ordered.build().as_mut());
+        assert!(matches!(result, SolveResult::Unsat));
+    }
+
+    #[test]
+    fn parsing_with_dedup_leaves_out_repeated_clauses() {
+        let input = "p cnf 3 6\n1 -2 0\n-2 1 0\n1 1 -2 0\n2 -2 3 0\n3 0\n-2 1 3 0\n";
+        let options = ParseOptions::default().dedup(true);
+        let formula = Formula::parse_dimacs_str_with(input, options).unwrap();
+        let clauses: Vec<_> = formula.clauses().map(|c| c.to_vec()).collect();
+        let lit = |id, negated| Literal::new(id, negated);
+        assert_eq!(clauses, vec![
+            vec![lit(0, false), lit(1, true)],
+            vec![lit(2, false)],
+            vec![lit(1, true), lit(0, false), lit(2, false)],
+        ]);
+        assert_eq!(formula.stats.duplicate_clauses, 3);
+
+        let formula = Formula::parse_dimacs_str(input).unwrap();
+        assert_eq!(formula.clauses().count(), 6);
+        assert_eq!(formula.stats.duplicate_clauses, 0);
+    }
+
+    #[test]
+    fn solver_leaves_out_repeated_clauses() {
+        let formula = Formula::parse_dimacs_str("p cnf 2 2\n1 2 0\n2 1 0\n").unwrap();
+        let mut solver = Solver::new(formula, Heuristic::Ordered.build());
+        let (x, y) = (Literal::new(0, false), Literal::new(1, false));
+        solver.add_clause([y, x, y]);
+        solver.add_clause([x, !x]);
+        solver.add_clause([!x, y]);
+        solver.add_clause([y, !x]);
+        assert_eq!(solver.formula().clauses().count(), 3);
+        assert_eq!(solver.stats().duplicate_clauses, 3);
+        assert!(matches!(solver.solve(), SolveResult::Sat(_)));
+        assert_eq!(solver.stats().duplicate_clauses, 3);
+    }
+
+    #[test]
+    fn fast_path_keeps_the_enabled_opt_in_passes() {
+        let input = "p cnf 5 2\n1 2 3 4 5 0\n1 2 0\n";
+        let config = SolverConfig::default().ternary_threshold(Some(3));
+        let mut formula = Formula::parse_dimacs_str(input).unwrap();
+        let stats = formula.preprocess(&config);
+        assert!(stats.subsumption.is_none() && stats.binary.is_none());
+        assert_eq!(stats.aux_vars, Some(2));
+
+        let mut formula = Formula::parse_dimacs_str(input).unwrap();
+        let stats = formula.preprocess(&config.fast_path_vars(0));
+        assert_eq!(stats.subsumption, Some((1, 0)));
+        assert!(stats.binary.is_some());
+    }
+
+    #[test]
+    fn solver_gives_up_while_finding_the_failed_assumptions() {
+        // The pigeonhole formula under the assumption, which is satisfiable without it
+        let solver = || {
+            let mut formula = Formula::new();
+            let pigeonhole = generate::pigeonhole(4);
+            for _ in 0..pigeonhole.num_vars() {
+                formula.new_var();
+            }
+            let assumption = formula.new_var().positive();
+            for clause in pigeonhole.clauses() {
+                formula.add_clause(clause.iter().copied().chain([!assumption]));
+            }
+            (Solver::new(formula, Heuristic::Ordered.build()), assumption)
+        };
+        let (mut unlimited, assumption) = solver();
+        assert!(matches!(unlimited.solve_under(&[assumption]), SolveResult::Unsat));
+        assert_eq!(unlimited.failed(), &[assumption]);
+
+        // The search proves unsatisfiability, but leaves too little of the limit to
+        // confirm that the assumption is needed
+        let (mut limited, _) = solver();
+        limited.set_conflict_limit(Some(unlimited.stats().conflicts + 1));
+        assert!(matches!(limited.solve_under(&[assumption]), SolveResult::Unknown));
+        assert!(limited.failed().is_empty());
+        limited.set_conflict_limit(None);
+        assert!(matches!(limited.solve(), SolveResult::Sat(_)));
+    }
+
+    #[test]
+    fn reseeding_varies_the_searches() {
+        let mut varied = 0;
+        for i in 0..20 {
+            let formula = generate::random_k_sat(20, 3, 3.0, i);
+            let models = |seed| {
+                let mut solver = Solver::new(formula.clone(), Heuristic::Dlis.build());
+                solver.set_reseeding(Some(seed));
+                (0..4).map(|id| {
+                    let assumption = Literal::new(id, id % 2 == 0);
+                    match solver.solve_under(&[assumption]) {
+                        SolveResult::Sat(model) => {
+                            assert!(testing::is_model(&formula, &model));
+                            assert!(model.assigned(assumption));
+                            Some(model.dimacs_literals())
+                        }
+                        SolveResult::Unsat => None,
+                        SolveResult::Unknown => panic!("No limits were set"),
+                    }
+                }).collect::<Vec<_>>()
+            };
+            let (first, again, other) = (models(1), models(1), models(2));
+            assert!(first == again);
+            // Every seed agrees on which queries are satisfiable
+            assert!(first.iter().zip(&other).all(|(a, b)| a.is_some() == b.is_some()));
+            varied += (first != other) as usize;
+        }
+        assert!(varied > 10);
+    }
+
+    #[test]
+    fn config_sets_the_limits() {
+        let config = SolverConfig::default().conflict_limit(Some(10)).chaos_seed(Some(3));
+        let mut solver = Solver::with_config(generate::pigeonhole(5), config);
+        assert!(matches!(solver.solve(), SolveResult::Unknown));
+        let config = config.conflict_limit(None).propagation_limit(Some(10));
+        let mut solver = Solver::with_config(generate::pigeonhole(5), config);
+        assert!(matches!(solver.solve(), SolveResult::Unknown));
+        let config = config.propagation_limit(None);
+        let mut solver = Solver::with_config(generate::pigeonhole(4), config);
+        assert!(matches!(solver.solve(), SolveResult::Unsat));
+    }
+}
@@ -0,0 +1,37 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::heuristic::DecisionHeuristic;
+use super::{Formula, Literal, SolveResult};
+
+impl Formula {
+    /// Finds the backbone of the formula, i.e. the literals which are true in every
+    /// model, ordered by variable. The literals of a first model are the candidates.
+    /// Each is tested by solving with its negation assumed, which either proves it or
+    /// gives a model ruling out every candidate that model disagrees with. Returns None
+    /// if the formula is unsatisfiable or the search gives up
+    pub fn backbone(&self, heuristic: &mut dyn DecisionHeuristic) -> Option<Vec<Literal>> {
+        let model = match self.clone().solve(heuristic) {
+            SolveResult::Sat(model) => model,
+            SolveResult::Unsat | SolveResult::Unknown => return None,
+        };
+        let mut candidates: Vec<_> = model.0.iter().enumerate()
+            .filter_map(|(id, negated)| negated.map(|n| Literal::new(id, n)))
+            .collect();
+
+        // Proven backbone literals are added as units to speed up the later tests
+        let mut formula = self.clone();
+        let mut backbone = vec![];
+        while let Some(lit) = candidates.pop() {
+            match formula.restricted(&[!lit]).solve(heuristic) {
+                SolveResult::Sat(model) => candidates.retain(|&l| model.assigned(l)),
+                SolveResult::Unsat => {
+                    formula.clauses.push([lit]);
+                    backbone.push(lit);
+                }
+                SolveResult::Unknown => return None,
+            }
+        }
+        backbone.sort_unstable();
+        Some(backbone)
+    }
+}
@@ -0,0 +1,281 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use super::two_sat::strongly_connected_components;
+use super::{ClauseDb, Formula, Literal};
+
+// Literals probed for hyper-binary resolvents, bounding the cost on large formulas
+const MAX_PROBES: usize = 1 << 12;
+// Literals visited when looking for a path which makes a binary clause redundant
+const MAX_REDUCTION_STEPS: usize = 1 << 8;
+
+/// Counts of the simplifications made by `simplify_binary`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BinaryStats {
+    /// Binary and unit clauses added by hyper-binary resolution
+    pub hyper_binary: usize,
+    /// Variables replaced by an equivalent literal
+    pub substituted: usize,
+    /// Binary clauses removed by transitive reduction
+    pub reduced: usize,
+}
+
+impl Formula {
+    /// Simplifies the formula using its binary implication graph, which has an edge
+    /// !a -> b and !b -> a for each clause (a, b). Adds hyper-binary resolvents,
+    /// replaces the literals on a cycle of implications by a single representative and
+    /// drops the binary clauses implied by the rest of the graph. The result is
+    /// equivalent. If some literal is equivalent to its negation, only an empty clause
+    /// is left instead
+    pub fn simplify_binary(&mut self) -> BinaryStats {
+        let hyper_binary = self.hyper_binary_resolution();
+        let substituted = match self.substitute_equivalent() {
+            Some(substituted) => substituted,
+            None => {
+                self.clauses = ClauseDb::unsatisfiable();
+                return BinaryStats { hyper_binary, substituted: 0, reduced: 0 };
+            }
+        };
+        let reduced = self.transitive_reduction();
+        BinaryStats { hyper_binary, substituted, reduced }
+    }
+
+    // The implication graph indexed by Literal::index, with each edge labeled by the
+    // index of the clause it comes from. A unit clause (a) gives the edge !a -> a
+    fn implication_graph(&self) -> Vec<Vec<(Literal, usize)>> {
+        let mut graph = vec![vec![]; 2 * self.num_vars()];
+        for (i, clause) in self.clauses.iter().enumerate() {
+            match *clause {
+                [a] => graph[(!a).index()].push((a, i)),
+                [a, b] if a != b && a != !b => {
+                    graph[(!a).index()].push((b, i));
+                    graph[(!b).index()].push((a, i));
+                }
+                _ => (),
+            }
+        }
+        graph
+    }
+
+    // For each probed literal l, adds (!l, m) whenever the literals implied by l through
+    // the implication graph falsify all but the literal m of a longer clause. If they
+    // falsify a whole clause or contain some literal along with its negation, l is a
+    // failed literal and (!l) is added instead. Returns the number of added clauses
+    fn hyper_binary_resolution(&mut self) -> usize {
+        let num_lits = 2 * self.num_vars();
+        let mut graph = self.implication_graph();
+        let mut occurrences = vec![vec![]; num_lits];
+        for (i, clause) in self.clauses.iter().enumerate().filter(|(_, c)| c.len() > 2) {
+            for lit in clause {
+                occurrences[lit.index()].push(i);
+            }
+        }
+
+        // Both are marked with the number of the probe which last reached them
+        let mut reached = vec![usize::MAX; num_lits];
+        let mut checked = vec![usize::MAX; self.clauses.len()];
+        let probes: Vec<_> = (0..num_lits)
+            .map(Literal::from_index)
+            .filter(|l| !graph[l.index()].is_empty())
+            .take(MAX_PROBES)
+            .collect();
+
+        let mut added = 0;
+        for (probe, lit) in probes.into_iter().enumerate() {
+            let mut implied = vec![lit];
+            reached[lit.index()] = probe;
+            let mut next = 0;
+            while let Some(&l) = implied.get(next) {
+                next += 1;
+                for &(m, _) in &graph[l.index()] {
+                    if reached[m.index()] != probe {
+                        reached[m.index()] = probe;
+                        implied.push(m);
+                    }
+                }
+            }
+
+            let is_reached = |l: Literal| reached[l.index()] == probe;
+            let mut failed = implied.iter().any(|&l| is_reached(!l));
+            let mut resolvents = vec![];
+            for &l in &implied {
+                if failed {
+                    break;
+                }
+                for &i in &occurrences[(!l).index()] {
+                    if checked[i] == probe {
+                        continue;
+                    }
+                    checked[i] = probe;
+                    let clause = &self.clauses[i];
+                    if clause.iter().any(|&m| is_reached(m)) {
+                        continue;
+                    }
+                    let mut open = clause.iter().copied().filter(|&m| !is_reached(!m));
+                    match (open.next(), open.next()) {
+                        (None, _) => {
+                            failed = true;
+                            break;
+                        }
+                        (Some(m), None) => resolvents.push(m),
+                        _ => (),
+                    }
+                }
+            }
+
+            if failed {
+                graph[lit.index()].push((!lit, self.clauses.len()));
+                self.clauses.push([!lit]);
+                added += 1;
+                continue;
+            }
+            resolvents.sort_by_key(|m| m.index());
+            resolvents.dedup();
+            for m in resolvents {
+                graph[lit.index()].push((m, self.clauses.len()));
+                graph[(!m).index()].push((!lit, self.clauses.len()));
+                self.clauses.push([!lit, m]);
+                added += 1;
+            }
+        }
+        added
+    }
+
+    // Replaces each literal by the smallest literal in its strongly connected component
+    // of the implication graph, since they must all take the same value. Components with
+    // frozen variables are represented by the smallest frozen literal instead, and frozen
+    // variables are never replaced. Each replaced variable keeps a pair of binary clauses
+    // tying it to its representative, so models still assign it. Returns the number of
+    // replaced variables, or None if some literal is in the same component as its negation
+    fn substitute_equivalent(&mut self) -> Option<usize> {
+        let graph: Vec<Vec<_>> = self.implication_graph().into_iter()
+            .map(|edges| edges.into_iter().map(|(l, _)| l.index()).collect())
+            .collect();
+        let component = strongly_connected_components(&graph);
+        if (0..self.num_vars()).any(|id| component[2 * id] == component[2 * id + 1]) {
+            return None;
+        }
+
+        // Literals are visited in increasing index, so the first one seen is the smallest,
+        // and frozen ones are visited first
+        let frozen = &self.frozen;
+        let mut representatives = vec![None; graph.len()];
+        let (first, rest): (Vec<_>, Vec<_>) = (0..graph.len())
+            .partition(|index| frozen.contains(&(index / 2)));
+        for index in first.into_iter().chain(rest) {
+            let lit = Literal::from_index(index);
+            representatives[component[index]].get_or_insert(lit);
+        }
+        let representative = |l: Literal| {
+            if frozen.contains(&l.id()) {
+                l
+            } else {
+                representatives[component[l.index()]].unwrap()
+            }
+        };
+        let replaced: Vec<_> = (0..self.num_vars())
+            .map(|id| Literal::new(id, false))
+            .filter(|&l| representative(l) != l)
+            .collect();
+        if replaced.is_empty() {
+            return Some(0);
+        }
+
+        for i in 0..self.clauses.len() {
+            for lit in &mut self.clauses[i] {
+                *lit = representative(*lit);
+            }
+            self.clauses.normalize(i);
+        }
+        // Literals of a variable are adjacent once normalized, so this finds tautologies
+        self.clauses.retain(|c| !c.windows(2).any(|w| w[0] == !w[1]));
+        for &lit in &replaced {
+            let rep = representative(lit);
+            self.clauses.push([!lit, rep]);
+            self.clauses.push([lit, !rep]);
+        }
+        Some(replaced.len())
+    }
+
+    // Removes each binary clause (a, b) for which the rest of the implication graph still
+    // has a path from !a to b, giving up on the search after MAX_REDUCTION_STEPS literals.
+    // Returns the number of removed clauses
+    fn transitive_reduction(&mut self) -> usize {
+        let graph = self.implication_graph();
+        let mut removed = vec![false; self.clauses.len()];
+        // Marked with the index of the clause whose search last visited them
+        let mut visited = vec![usize::MAX; graph.len()];
+        for (i, clause) in self.clauses.iter().enumerate() {
+            let (a, b) = match *clause {
+                [a, b] if a != b && a != !b => (a, b),
+                _ => continue,
+            };
+
+            let mut stack = vec![!a];
+            visited[(!a).index()] = i;
+            let mut steps = 0;
+            'search: while let Some(l) = stack.pop() {
+                steps += 1;
+                if steps > MAX_REDUCTION_STEPS {
+                    break;
+                }
+                for &(m, j) in &graph[l.index()] {
+                    if j == i || removed[j] || visited[m.index()] == i {
+                        continue;
+                    }
+                    if m == b {
+                        removed[i] = true;
+                        break 'search;
+                    }
+                    visited[m.index()] = i;
+                    stack.push(m);
+                }
+            }
+        }
+
+        let mut index = 0;
+        self.clauses.retain(|_| {
+            index += 1;
+            !removed[index - 1]
+        });
+        removed.iter().filter(|&&r| r).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::Rng;
+    use crate::solver::testing;
+    use crate::solver::Literal;
+
+    #[test]
+    fn simplifies_small_instances() {
+        // Probing 1 implies 2 and 3, which leave only 4 in the last clause
+        let mut formula = testing::formula("p cnf 4 3\n-1 2 0\n-1 3 0\n-2 -3 4 0\n");
+        assert!(formula.simplify_binary().hyper_binary >= 1);
+        assert!(formula.clauses().any(|c| c == [Literal::from_var(-1), Literal::from_var(4)]));
+        // 1, 2 and 3 imply each other in a cycle
+        let mut formula = testing::formula("p cnf 3 4\n-1 2 0\n-2 3 0\n-3 1 0\n1 2 3 0\n");
+        let models = testing::count_models(&formula);
+        assert_eq!(formula.simplify_binary().substituted, 2);
+        assert_eq!(testing::count_models(&formula), models);
+        let mut formula = testing::formula("p cnf 2 4\n1 2 0\n-1 2 0\n1 -2 0\n-1 -2 0\n");
+        formula.simplify_binary();
+        assert!(formula.clauses().any(|c| c.is_empty()));
+    }
+
+    #[test]
+    fn keeps_the_models() {
+        let mut rng = Rng::new(11);
+        let (mut hyper_binary, mut substituted, mut reduced) = (0, 0, 0);
+        for _ in 0..300 {
+            let mut formula = testing::random_formula(&mut rng, 7, 9, 3);
+            let models = testing::count_models(&formula);
+            let stats = formula.simplify_binary();
+            assert_eq!(testing::count_models(&formula), models);
+            hyper_binary += stats.hyper_binary;
+            substituted += stats.substituted;
+            reduced += stats.reduced;
+        }
+        assert!(hyper_binary > 0 && substituted > 0 && reduced > 0);
+    }
+}
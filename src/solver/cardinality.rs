@@ -0,0 +1,190 @@
+use core::convert::TryFrom;
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+use super::{Assignment, ExternalPropagator, Formula, Literal};
+
+/// The ways of encoding cardinality constraints into clauses
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum CardinalityEncoding {
+    /// Sinz's sequential counter, which counts the true literals one at a time with
+    /// about n * k auxiliary variables
+    SequentialCounter,
+    /// Bailleux and Boufkhad's totalizer, which counts the true literals in a balanced
+    /// tree, with at most about n * log n auxiliary variables
+    Totalizer,
+}
+
+impl Formula {
+    /// Adds clauses, and auxiliary variables, which hold exactly if at most k of the
+    /// literals are true. The auxiliary variables are defined both ways, so once the
+    /// literals are assigned they have only one value left
+    pub fn add_at_most_k(&mut self, literals: &[Literal], k: usize, encoding: CardinalityEncoding) {
+        if k >= literals.len() {
+            return;
+        }
+        match encoding {
+            CardinalityEncoding::SequentialCounter => {
+                let terms: Vec<_> = literals.iter().map(|&lit| (1, lit)).collect();
+                at_most_weight(self, &terms, k as u64);
+            }
+            CardinalityEncoding::Totalizer => {
+                let counts = self.totalize(literals, k + 1);
+                if let Some(&over) = counts.get(k) {
+                    self.add_clause([!over]);
+                }
+            }
+        }
+    }
+
+    /// Adds clauses, and auxiliary variables, which hold exactly if at least k of the
+    /// literals are true, i.e. at most all but k of their negations are
+    pub fn add_at_least_k(&mut self, literals: &[Literal], k: usize, encoding: CardinalityEncoding) {
+        match literals.len().checked_sub(k) {
+            Some(rest) => {
+                let negations: Vec<_> = literals.iter().map(|&lit| !lit).collect();
+                self.add_at_most_k(&negations, rest, encoding);
+            }
+            None => self.add_clause([]),
+        }
+    }
+
+    // Returns literals whose j-th is true exactly if more than j of the given literals
+    // are, counting no higher than the limit
+    fn totalize(&mut self, literals: &[Literal], limit: usize) -> Vec<Literal> {
+        if literals.len() == 1 {
+            return literals.to_vec();
+        }
+        let (left, right) = literals.split_at(literals.len() / 2);
+        let left = self.totalize(left, limit);
+        let right = self.totalize(right, limit);
+        let counts: Vec<_> = (0..literals.len().min(limit))
+            .map(|_| self.new_var().positive())
+            .collect();
+        // With i true on the left and j on the right, more than i + j - 1 are true, and
+        // with at most i on the left and at most j on the right, at most i + j are
+        for i in 0..=left.len() {
+            for j in 0..=right.len() {
+                let (at_least_left, at_least_right) = (i.checked_sub(1), j.checked_sub(1));
+                if let Some(&count) = (i + j).checked_sub(1).and_then(|s| counts.get(s)) {
+                    self.add_clause([count].iter().copied()
+                        .chain(at_least_left.map(|i| !left[i]))
+                        .chain(at_least_right.map(|j| !right[j])));
+                }
+                if let Some(&count) = counts.get(i + j) {
+                    self.add_clause([!count].iter().copied()
+                        .chain(left.get(i).copied())
+                        .chain(right.get(j).copied()));
+                }
+            }
+        }
+        counts
+    }
+}
+
+/// Cardinality constraints checked during the search instead of being encoded, for
+/// `Formula::solve_with_propagator`. Whenever the assignment violates a constraint, the
+/// propagator adds the clause ruling out the literals responsible, so no auxiliary
+/// variables are needed
+#[derive(Default)]
+pub struct CardinalityConstraints {
+    // Lists of literals of which at most the given number may be true
+    at_most: Vec<(Vec<Literal>, usize)>,
+    // Whether some constraint demands more true literals than it has
+    unsatisfiable: bool,
+    // The clauses added so far, which the search keeps
+    added: BTreeSet<Vec<Literal>>,
+}
+
+impl CardinalityConstraints {
+    /// Creates a propagator without constraints
+    pub fn new() -> Self {
+        CardinalityConstraints::default()
+    }
+
+    /// Demands that at most k of the literals are true
+    pub fn add_at_most_k(&mut self, literals: &[Literal], k: usize) {
+        self.at_most.push((literals.to_vec(), k));
+    }
+
+    /// Demands that at least k of the literals are true
+    pub fn add_at_least_k(&mut self, literals: &[Literal], k: usize) {
+        match literals.len().checked_sub(k) {
+            Some(rest) => self.at_most.push((literals.iter().map(|&lit| !lit).collect(), rest)),
+            None => self.unsatisfiable = true,
+        }
+    }
+
+    // A clause not added before which the assignment falsifies, if it violates some
+    // constraint
+    fn violated(&mut self, assignment: &Assignment) -> Option<Vec<Literal>> {
+        let added = &mut self.added;
+        if self.unsatisfiable {
+            return Some(vec![]).filter(|clause| added.insert(clause.clone()));
+        }
+        self.at_most.iter().find_map(|(literals, k)| {
+            let mut clause: Vec<_> = literals.iter()
+                .filter(|&&lit| assignment.assigned(lit))
+                .map(|&lit| !lit)
+                .take(k + 1)
+                .collect();
+            if clause.len() <= *k {
+                return None;
+            }
+            clause.sort_unstable();
+            Some(clause).filter(|clause| added.insert(clause.clone()))
+        })
+    }
+}
+
+impl ExternalPropagator for CardinalityConstraints {
+    fn external_clause(&mut self, assignment: &Assignment) -> Option<Vec<Literal>> {
+        self.violated(assignment)
+    }
+
+    fn check_model(&mut self, model: &Assignment) -> Option<Vec<Literal>> {
+        self.violated(model)
+    }
+}
+
+// Adds the sequential weight counter encoding of the constraint that the weights of the
+// true literals sum to at most the bound. After each literal, the j-th of a fresh set of
+// counters is true exactly if the literals so far weigh more than j. Defining the
+// counters both ways leaves the search no choice once the literals are assigned, which
+// matters since it has no propagation to rule out wrong values early
+pub(super) fn at_most_weight(formula: &mut Formula, terms: &[(u64, Literal)], bound: u64) {
+    let bound = usize::try_from(bound).unwrap_or(usize::MAX);
+    let mut counters: Vec<Literal> = vec![];
+    for (i, &(weight, lit)) in terms.iter().enumerate() {
+        let weight = usize::try_from(weight).unwrap_or(usize::MAX);
+        if weight > bound {
+            formula.add_clause([!lit]);
+            continue;
+        }
+        if let Some(&full) = counters.get(bound - weight) {
+            formula.add_clause([!lit, !full]);
+        }
+        if i + 1 == terms.len() {
+            break;
+        }
+        let next: Vec<_> = (0..bound).map(|_| formula.new_var().positive()).collect();
+        for (j, &counter) in next.iter().enumerate() {
+            let before = counters.get(j).copied();
+            // Whether the literal pushes the weight past j, given that it is true
+            let below = j.checked_sub(weight).map(|k| counters.get(k).copied());
+            if let Some(before) = before {
+                formula.add_clause([!before, counter]);
+            }
+            match below {
+                None => formula.add_clause([!lit, counter]),
+                Some(Some(below)) => formula.add_clause([!lit, !below, counter]),
+                Some(None) => (),
+            }
+            formula.add_clause([!counter, lit].iter().copied().chain(before));
+            if let Some(below) = below {
+                formula.add_clause([!counter].iter().copied().chain(before).chain(below));
+            }
+        }
+        counters = next;
+    }
+}
@@ -0,0 +1,185 @@
+use core::iter::FromIterator;
+use core::mem;
+use core::ops::{Index, IndexMut};
+use alloc::vec::Vec;
+use crate::rng::Rng;
+use super::Literal;
+
+// The header in front of the literals of each clause, made of its length and its flags,
+// the rest of whose bits are free for e.g. the LBD of learned clauses
+const HEADER_LEN: usize = 2;
+// Set in the flags of removed clauses, so references held elsewhere can tell
+const DELETED: u32 = 1;
+
+// Where the header of a clause starts in the arena
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct ClauseRef(u32);
+
+impl ClauseRef {
+    fn offset(self) -> usize {
+        self.0 as usize
+    }
+}
+
+// The clauses of a formula, stored one after the other in a single flat arena instead of
+// a vector each, so the search walks through contiguous memory. Clauses are indexed by
+// their position, which they keep in the order they were added, while removing a clause
+// or some of its literals leaves a gap in the arena, which is reclaimed by compacting it
+// once the gaps take up more room than the clauses. Compacting moves the clauses but
+// keeps their positions, so lists of positions such as the occurrence lists stay valid
+#[derive(Clone, Default)]
+pub(super) struct ClauseDb {
+    // Headers, with their words stored as raw literals, each followed by the literals
+    arena: Vec<Literal>,
+    // The clause at each position
+    refs: Vec<ClauseRef>,
+    // The number of words of the arena in gaps
+    wasted: usize,
+    // The bytes returned by compacting so far
+    reclaimed: usize,
+}
+
+impl ClauseDb {
+    pub(super) fn new() -> Self {
+        ClauseDb::default()
+    }
+
+    // The database holding only the empty clause, which is unsatisfiable
+    pub(super) fn unsatisfiable() -> Self {
+        let mut clauses = ClauseDb::new();
+        clauses.push(None);
+        clauses
+    }
+
+    // Adds a clause at the last position
+    pub(super) fn push(&mut self, literals: impl IntoIterator<Item = Literal>) {
+        let start = self.arena.len();
+        assert!(start <= u32::MAX as usize, "The clauses don't fit in the arena");
+        self.arena.extend([Literal(0), Literal(0)].iter().copied());
+        self.arena.extend(literals);
+        self.arena[start] = Literal((self.arena.len() - start - HEADER_LEN) as u32);
+        self.refs.push(ClauseRef(start as u32));
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.refs.len()
+    }
+
+    pub(super) fn iter(&self) -> impl Iterator<Item = &[Literal]> {
+        self.refs.iter().map(move |&cref| self.literals(cref))
+    }
+
+    // Randomly reorders the clauses and the literals within each
+    pub(super) fn shuffle(&mut self, rng: &mut Rng) {
+        rng.shuffle(&mut self.refs);
+        for i in 0..self.len() {
+            rng.shuffle(&mut self[i]);
+        }
+    }
+
+    // Removes the clauses for which the predicate is false, keeping the order of the rest
+    pub(super) fn retain(&mut self, mut keep: impl FnMut(&[Literal]) -> bool) {
+        let mut refs = mem::take(&mut self.refs);
+        refs.retain(|&cref| {
+            let kept = keep(self.literals(cref));
+            if !kept {
+                self.wasted += HEADER_LEN + self.literals(cref).len();
+                self.arena[cref.offset() + 1].0 |= DELETED;
+            }
+            kept
+        });
+        self.refs = refs;
+        self.compact_if_wasteful();
+    }
+
+    // Removes the literals of the clause at the position for which the predicate is false
+    pub(super) fn retain_literals(&mut self, i: usize, mut keep: impl FnMut(Literal) -> bool) {
+        let start = self.refs[i].offset() + HEADER_LEN;
+        let len = self[i].len();
+        let mut kept = 0;
+        for k in start..start + len {
+            let lit = self.arena[k];
+            if keep(lit) {
+                self.arena[start + kept] = lit;
+                kept += 1;
+            }
+        }
+        self.arena[start - HEADER_LEN] = Literal(kept as u32);
+        self.wasted += len - kept;
+        self.compact_if_wasteful();
+    }
+
+    // Sorts the literals of the clause at the position and removes duplicates, so that
+    // equivalent clauses become equal
+    pub(super) fn normalize(&mut self, i: usize) {
+        self[i].sort_unstable_by_key(|l| l.index());
+        let mut last = None;
+        self.retain_literals(i, |lit| last.replace(lit) != Some(lit));
+    }
+
+    // The bytes returned by compacting the arena so far
+    pub(super) fn reclaimed_bytes(&self) -> usize {
+        self.reclaimed
+    }
+
+    // Moves the clauses together to the front of a new arena of just the size they need,
+    // dropping the gaps along with any spare capacity
+    pub(super) fn compact(&mut self) {
+        let mut arena = Vec::with_capacity(self.arena.len() - self.wasted);
+        for cref in &mut self.refs {
+            let start = cref.offset();
+            let end = start + HEADER_LEN + self.arena[start].0 as usize;
+            *cref = ClauseRef(arena.len() as u32);
+            arena.extend_from_slice(&self.arena[start..end]);
+        }
+        let freed = self.arena.capacity() - arena.capacity();
+        self.reclaimed += freed * mem::size_of::<Literal>();
+        self.arena = arena;
+        self.wasted = 0;
+    }
+
+    fn literals(&self, cref: ClauseRef) -> &[Literal] {
+        let start = cref.offset() + HEADER_LEN;
+        &self.arena[start..start + self.arena[cref.offset()].0 as usize]
+    }
+
+    // Compacts the arena once the gaps take up more room than the clauses, which keeps
+    // it at most twice as large as needed
+    fn compact_if_wasteful(&mut self) {
+        if 2 * self.wasted > self.arena.len() {
+            self.compact();
+        }
+    }
+}
+
+impl Index<usize> for ClauseDb {
+    type Output = [Literal];
+
+    fn index(&self, i: usize) -> &[Literal] {
+        self.literals(self.refs[i])
+    }
+}
+
+impl IndexMut<usize> for ClauseDb {
+    fn index_mut(&mut self, i: usize) -> &mut [Literal] {
+        let start = self.refs[i].offset() + HEADER_LEN;
+        let len = self.arena[start - HEADER_LEN].0 as usize;
+        &mut self.arena[start..start + len]
+    }
+}
+
+impl<C: AsRef<[Literal]>> FromIterator<C> for ClauseDb {
+    fn from_iter<I: IntoIterator<Item = C>>(clauses: I) -> Self {
+        let mut db = ClauseDb::new();
+        db.extend(clauses);
+        db
+    }
+}
+
+impl<C: AsRef<[Literal]>> Extend<C> for ClauseDb {
+    fn extend<I: IntoIterator<Item = C>>(&mut self, clauses: I) {
+        for clause in clauses {
+            self.push(clause.as_ref().iter().copied());
+        }
+    }
+}
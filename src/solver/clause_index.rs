@@ -0,0 +1,71 @@
+use core::mem;
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::rng;
+use super::{Clause, ClauseDb, Literal};
+
+// The clauses of a database by the hash of their literals, so that a clause about to be
+// added can be recognized as a duplicate without comparing it with every clause.
+// Clauses are compared normalized, so the same literals in another order, or repeated,
+// make a duplicate too
+#[derive(Clone, Default)]
+pub(super) struct ClauseIndex {
+    // An open addressing table of the hash and position of each indexed clause, probed
+    // linearly from the hash, since a tree makes deduplicating large inputs slow
+    slots: Vec<(u64, usize)>,
+    // The number of indexed clauses
+    len: usize,
+}
+
+// The position of an empty slot
+const EMPTY: usize = usize::MAX;
+
+impl ClauseIndex {
+    // Indexes the normalized clause as the one at the position of the database, unless it
+    // is a tautology or a duplicate of an indexed clause, in which case it needn't be
+    // added and false is returned
+    pub(super) fn insert(&mut self, clause: &[Literal], position: usize, clauses: &ClauseDb)
+        -> bool {
+        if clause.windows(2).any(|pair| pair[0] == !pair[1]) {
+            return false;
+        }
+        if 2 * (self.len + 1) > self.slots.len() {
+            self.grow();
+        }
+        let hash = hash(clause);
+        let mask = self.slots.len() - 1;
+        let mut slot = hash as usize & mask;
+        loop {
+            let (slot_hash, slot_position) = self.slots[slot];
+            if slot_position == EMPTY {
+                self.slots[slot] = (hash, position);
+                self.len += 1;
+                return true;
+            }
+            if slot_hash == hash && clauses[slot_position].normalized() == clause {
+                return false;
+            }
+            slot = (slot + 1) & mask;
+        }
+    }
+
+    // Doubles the table, keeping it at most half full
+    fn grow(&mut self) {
+        let slots = vec![(0, EMPTY); (2 * self.slots.len()).max(16)];
+        let mask = slots.len() - 1;
+        for (hash, position) in mem::replace(&mut self.slots, slots) {
+            if position != EMPTY {
+                let mut slot = hash as usize & mask;
+                while self.slots[slot].1 != EMPTY {
+                    slot = (slot + 1) & mask;
+                }
+                self.slots[slot] = (hash, position);
+            }
+        }
+    }
+}
+
+// A hash of the normalized clause, which is stable across runs and platforms
+pub(super) fn hash(clause: &[Literal]) -> u64 {
+    clause.iter().fold(0, |hash, lit| rng::mix(hash ^ lit.index() as u64))
+}
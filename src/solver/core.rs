@@ -0,0 +1,196 @@
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::heuristic::DecisionHeuristic;
+use super::{Budget, Clause, Formula, Literal, SolveResult};
+
+/// The outcome of minimizing an unsatisfiable set of clauses or assumptions
+pub enum CoreResult<T> {
+    /// The set is unsatisfiable, and this is a minimal part of it which still is
+    Minimal(T),
+    /// The whole set is satisfiable, so there is no core
+    Sat,
+    /// The limits or the interrupt of the formula stopped the search before the core
+    /// was minimal
+    Unknown,
+}
+
+impl Formula {
+    /// Given an older, satisfiable version of this formula, finds a minimal set of the
+    /// clauses added since which makes the clauses the versions share unsatisfiable.
+    /// Returns those clauses as a formula. The searches share the limits of the formula
+    pub fn unsat_delta(&self, older: &Formula, heuristic: &mut dyn DecisionHeuristic)
+        -> CoreResult<Formula> {
+        let old: BTreeSet<_> = older.clauses.iter().map(Clause::normalized).collect();
+        let (shared, added) = self.clauses.iter().map(<[Literal]>::to_vec)
+            .partition(|c| old.contains(&c.normalized()));
+        self.minimize_core(shared, added, heuristic, &mut self.budget.clone())
+            .map(|delta| self.with_clauses(delta.into_iter().collect()))
+    }
+
+    /// Finds a minimal unsatisfiable subset of the clauses, from which no clause can be
+    /// dropped without making it satisfiable. The searches share the limits of the formula
+    pub fn minimal_unsat_subset(&self, heuristic: &mut dyn DecisionHeuristic)
+        -> CoreResult<Formula> {
+        let clauses = self.clauses.iter().map(<[Literal]>::to_vec).collect();
+        self.minimize_core(vec![], clauses, heuristic, &mut self.budget.clone())
+            .map(|core| self.with_clauses(core.into_iter().collect()))
+    }
+
+    /// Finds a minimal set of the assumed literals under which the formula is
+    /// unsatisfiable, which is empty if the formula is unsatisfiable by itself.
+    /// The searches share the limits of the formula
+    pub fn failed_assumptions(&self, assumptions: &[Literal], heuristic: &mut dyn DecisionHeuristic)
+        -> CoreResult<Vec<Literal>> {
+        self.failed_assumptions_sharing(assumptions, heuristic, &mut self.budget.clone())
+    }
+
+    // Like failed_assumptions, but within a budget shared with other searches
+    pub(super) fn failed_assumptions_sharing(&self, assumptions: &[Literal],
+        heuristic: &mut dyn DecisionHeuristic, budget: &mut Budget) -> CoreResult<Vec<Literal>> {
+        let clauses = self.clauses.iter().map(<[Literal]>::to_vec).collect();
+        let units = assumptions.iter().map(|&l| vec![l]).collect();
+        self.minimize_core(clauses, units, heuristic, budget)
+            .map(|failed| failed.into_iter().map(|c| c[0]).collect())
+    }
+
+    // Deletion-based core minimization: returns a subset-minimal set of the candidate
+    // clauses which is unsatisfiable together with the fixed clauses. Each search takes
+    // what is left of the budget, so the minimization as a whole keeps to the limits
+    fn minimize_core(&self, fixed: Vec<Vec<Literal>>, mut candidates: Vec<Vec<Literal>>,
+                     heuristic: &mut dyn DecisionHeuristic, budget: &mut Budget)
+        -> CoreResult<Vec<Vec<Literal>>> {
+        let mut solve = |candidates: &[Vec<Literal>]| {
+            let clauses = fixed.iter().chain(candidates).collect();
+            self.with_clauses(clauses).solve_sharing(budget, heuristic).0
+        };
+
+        match solve(&candidates) {
+            SolveResult::Sat(_) => return CoreResult::Sat,
+            SolveResult::Unknown => return CoreResult::Unknown,
+            SolveResult::Unsat => (),
+        }
+        let mut i = 0;
+        while i < candidates.len() {
+            let clause = candidates.remove(i);
+            match solve(&candidates) {
+                SolveResult::Sat(_) => {
+                    candidates.insert(i, clause);
+                    i += 1;
+                }
+                SolveResult::Unknown => return CoreResult::Unknown,
+                SolveResult::Unsat => (),
+            }
+        }
+        CoreResult::Minimal(candidates)
+    }
+}
+
+impl<T> CoreResult<T> {
+    // Applies the function to the minimal set
+    fn map<U>(self, f: impl FnOnce(T) -> U) -> CoreResult<U> {
+        match self {
+            CoreResult::Minimal(core) => CoreResult::Minimal(f(core)),
+            CoreResult::Sat => CoreResult::Sat,
+            CoreResult::Unknown => CoreResult::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::sync::atomic::AtomicBool;
+    use crate::generate;
+    use crate::heuristic::Heuristic;
+    use crate::rng::Rng;
+    use crate::solver::testing;
+    use crate::solver::{Formula, Literal};
+    use super::CoreResult;
+
+    // The formula over the same variables without the clause at the index
+    fn without(formula: &Formula, index: usize) -> Formula {
+        let mut smaller = Formula::new();
+        for _ in 0..formula.num_vars() {
+            smaller.new_var();
+        }
+        for (i, clause) in formula.clauses().enumerate() {
+            if i != index {
+                smaller.add_clause(clause.iter().copied());
+            }
+        }
+        smaller
+    }
+
+    #[test]
+    fn minimal_unsat_subset_agrees_with_enumeration() {
+        let mut rng = Rng::new(11);
+        let mut unsat = 0;
+        for _ in 0..200 {
+            let formula = testing::random_formula(&mut rng, 6, 12, 3);
+            match formula.minimal_unsat_subset(Heuristic::Ordered.build().as_mut()) {
+                CoreResult::Minimal(core) => {
+                    unsat += 1;
+                    assert!(!testing::satisfiable(&core));
+                    let clauses: Vec<_> = formula.clauses().collect();
+                    assert!(core.clauses().all(|c| clauses.contains(&c)));
+                    let num_clauses = core.clauses().count();
+                    assert!((0..num_clauses).all(|i| testing::satisfiable(&without(&core, i))));
+                }
+                CoreResult::Sat => assert!(testing::satisfiable(&formula)),
+                CoreResult::Unknown => panic!("No limits were set"),
+            }
+        }
+        assert!(unsat > 20 && unsat < 180);
+    }
+
+    #[test]
+    fn failed_assumptions_agree_with_enumeration() {
+        let mut rng = Rng::new(12);
+        let mut unsat = 0;
+        for _ in 0..200 {
+            let formula = testing::random_formula(&mut rng, 6, 8, 3);
+            let assumptions: Vec<_> = (0..3)
+                .map(|_| Literal::new(rng.below(6), rng.chance(0.5)))
+                .collect();
+            let mut heuristic = Heuristic::Ordered.build();
+            match formula.failed_assumptions(&assumptions, heuristic.as_mut()) {
+                CoreResult::Minimal(failed) => {
+                    unsat += 1;
+                    assert!(failed.iter().all(|l| assumptions.contains(l)));
+                    assert!(!testing::satisfiable(&formula.restricted(&failed)));
+                    for i in 0..failed.len() {
+                        let mut fewer = failed.clone();
+                        fewer.remove(i);
+                        assert!(testing::satisfiable(&formula.restricted(&fewer)));
+                    }
+                }
+                CoreResult::Sat => assert!(testing::satisfiable(&formula.restricted(&assumptions))),
+                CoreResult::Unknown => panic!("No limits were set"),
+            }
+        }
+        assert!(unsat > 20 && unsat < 180);
+    }
+
+    #[test]
+    fn minimization_keeps_to_the_limits() {
+        let heuristic = || Heuristic::Ordered.build();
+        let formula = generate::pigeonhole(4);
+        assert!(matches!(formula.minimal_unsat_subset(heuristic().as_mut()),
+            CoreResult::Minimal(_)));
+        let (_, stats) = formula.clone().solve_with_stats(heuristic().as_mut());
+
+        // The first search proves the formula unsatisfiable, but leaves too little of the
+        // limit for the minimization
+        let mut limited = formula.clone();
+        limited.set_conflict_limit(Some(stats.conflicts + 1));
+        assert!(matches!(limited.minimal_unsat_subset(heuristic().as_mut()),
+            CoreResult::Unknown));
+
+        let mut interrupted = formula;
+        interrupted.set_interrupt(Some(Arc::new(AtomicBool::new(true))));
+        assert!(matches!(interrupted.minimal_unsat_subset(heuristic().as_mut()),
+            CoreResult::Unknown));
+    }
+}
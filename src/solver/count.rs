@@ -0,0 +1,213 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::heuristic::DecisionHeuristic;
+use super::{Budget, Clause, Formula, Literal, Var};
+
+// Clauses as sorted lists of literals, which are equal for equal sets of clauses
+// once the list of clauses is sorted too
+type Clauses = Vec<Vec<Literal>>;
+
+impl Formula {
+    /// Counts the assignments to all variables of the formula which satisfy it. The
+    /// search propagates units, splits the clauses into components without shared
+    /// variables, whose counts multiply, and caches the count of each component it meets.
+    /// Returns None if the count doesn't fit in a u128
+    pub fn count_models(&self) -> Option<u128> {
+        let clauses = self.clauses.iter()
+            .map(|c| c.normalized())
+            .filter(|c| !c.windows(2).any(|w| w[0].id() == w[1].id()))
+            .collect();
+        count(clauses, self.num_vars(), &mut BTreeMap::new())
+    }
+
+    /// Counts the assignments to the given variables which extend to models, by
+    /// enumerating them as in `iter_projected_models`. A model which leaves some of the
+    /// variables unassigned counts once for each way of assigning them. Like
+    /// `count_models`, this ignores the limits of the search, so the count is exact.
+    /// Returns None if the count doesn't fit in a u128
+    pub fn count_projected_models(&self, vars: &[Var], heuristic: &mut dyn DecisionHeuristic)
+        -> Option<u128> {
+        let mut formula = self.clone();
+        formula.budget = Budget::default();
+        let mut projection = vec![false; self.num_vars()];
+        for var in vars {
+            projection[var.0] = true;
+        }
+        formula.iter_projected_models(vars, heuristic).try_fold(0u128, |total, model| {
+            let free = model.0.iter().zip(&projection)
+                .filter(|&(value, &kept)| kept && value.is_none())
+                .count();
+            total.checked_add(1u128.checked_shl(free as u32)?)
+        })
+    }
+}
+
+// The number of assignments to num_vars variables, including those of the clauses,
+// which satisfy the clauses
+fn count(mut clauses: Clauses, mut num_vars: usize, cache: &mut BTreeMap<Clauses, u128>)
+    -> Option<u128> {
+    loop {
+        if clauses.iter().any(Vec::is_empty) {
+            return Some(0);
+        }
+        match clauses.iter().find(|c| c.len() == 1) {
+            Some(unit) => {
+                clauses = condition(&clauses, unit[0]);
+                num_vars -= 1;
+            }
+            None => break,
+        }
+    }
+
+    let components = components(clauses);
+    let free = num_vars - components.iter().map(|(_, vars)| vars).sum::<usize>();
+    let mut total = 1u128.checked_shl(free as u32)?;
+    for (component, num_vars) in components {
+        if total == 0 {
+            break;
+        }
+        total = total.checked_mul(count_component(component, num_vars, cache)?)?;
+    }
+    Some(total)
+}
+
+// The number of assignments to the variables of a connected, sorted set of clauses
+// without units which satisfy them
+fn count_component(clauses: Clauses, num_vars: usize, cache: &mut BTreeMap<Clauses, u128>)
+    -> Option<u128> {
+    if let Some(&known) = cache.get(&clauses) {
+        return Some(known);
+    }
+    let mut occurrences = BTreeMap::new();
+    for lit in clauses.iter().flatten() {
+        *occurrences.entry(lit.id()).or_insert(0) += 1;
+    }
+    let (&id, _) = occurrences.iter().max_by_key(|&(&id, &n)| (n, core::cmp::Reverse(id)))?;
+    let lit = Literal::new(id, false);
+    let total = count(condition(&clauses, lit), num_vars - 1, cache)?
+        .checked_add(count(condition(&clauses, !lit), num_vars - 1, cache)?)?;
+    cache.insert(clauses, total);
+    Some(total)
+}
+
+// The clauses with the literal set to true, which keeps them sorted
+fn condition(clauses: &[Vec<Literal>], lit: Literal) -> Clauses {
+    clauses.iter()
+        .filter(|c| !c.contains(&lit))
+        .map(|c| c.iter().copied().filter(|&l| l != !lit).collect())
+        .collect()
+}
+
+// Splits the clauses into groups connected by shared variables, each sorted and without
+// duplicates, along with its number of variables
+fn components(clauses: Clauses) -> Vec<(Clauses, usize)> {
+    // The clauses of each variable, and the component of each clause once found
+    let mut occurrences: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (i, clause) in clauses.iter().enumerate() {
+        for lit in clause {
+            occurrences.entry(lit.id()).or_default().push(i);
+        }
+    }
+    let mut component = vec![None; clauses.len()];
+    let mut num_components = 0;
+    let mut num_vars = vec![];
+    for start in 0..clauses.len() {
+        if component[start].is_some() {
+            continue;
+        }
+        component[start] = Some(num_components);
+        let mut stack = vec![start];
+        let mut vars = 0;
+        while let Some(i) = stack.pop() {
+            for lit in &clauses[i] {
+                if let Some(others) = occurrences.remove(&lit.id()) {
+                    vars += 1;
+                    for j in others {
+                        if component[j].is_none() {
+                            component[j] = Some(num_components);
+                            stack.push(j);
+                        }
+                    }
+                }
+            }
+        }
+        num_vars.push(vars);
+        num_components += 1;
+    }
+
+    let mut components: Vec<_> = num_vars.into_iter().map(|vars| (vec![], vars)).collect();
+    for (clause, i) in clauses.into_iter().zip(component) {
+        components[i.unwrap()].0.push(clause);
+    }
+    for (clauses, _) in &mut components {
+        clauses.sort_unstable();
+        clauses.dedup();
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeSet;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use crate::heuristic::Heuristic;
+    use crate::rng::Rng;
+    use crate::solver::testing;
+    use crate::solver::{CountingProblem, Formula, Var};
+
+    #[test]
+    fn counts_small_instances() {
+        assert_eq!(testing::formula("p cnf 3 0\n").count_models(), Some(8));
+        assert_eq!(testing::formula("p cnf 3 1\n1 2 0\n").count_models(), Some(6));
+        // Two components with 3 models each, and a free variable
+        let formula = testing::formula("p cnf 5 2\n1 2 0\n-3 -4 0\n");
+        assert_eq!(formula.count_models(), Some(18));
+        assert_eq!(testing::formula("p cnf 1 2\n1 0\n-1 0\n").count_models(), Some(0));
+        assert_eq!(testing::formula("p cnf 2 1\n1 -1 0\n").count_models(), Some(4));
+        assert_eq!(testing::formula("p cnf 127 0\n").count_models(), Some(1 << 127));
+        assert_eq!(testing::formula("p cnf 128 0\n").count_models(), None);
+    }
+
+    #[test]
+    fn agrees_with_enumeration() {
+        let mut rng = Rng::new(13);
+        for _ in 0..300 {
+            let formula = testing::random_formula(&mut rng, 9, 8, 3);
+            assert_eq!(formula.count_models(), Some(testing::count_models(&formula)));
+        }
+    }
+
+    #[test]
+    fn projected_counts_agree_with_enumeration() {
+        let mut rng = Rng::new(29);
+        for _ in 0..200 {
+            let num_vars = 7;
+            let formula = testing::random_formula(&mut rng, num_vars, 7, 3);
+            let vars: Vec<_> = (0..num_vars).filter(|_| rng.chance(0.5)).map(Var).collect();
+            let projections: BTreeSet<Vec<bool>> = (0..1 << num_vars)
+                .map(|bits| testing::values(num_vars, bits))
+                .filter(|values| testing::satisfied_by(&formula, values))
+                .map(|values| vars.iter().map(|var| values[var.0]).collect())
+                .collect();
+            let count = formula.count_projected_models(&vars, Heuristic::Ordered.build().as_mut());
+            assert_eq!(count, Some(projections.len() as u128));
+        }
+    }
+
+    #[test]
+    fn parses_counting_problems() {
+        let parse = Formula::parse_counting_problem_str;
+        assert_eq!(parse("c t mc\np cnf 2 0\n"), Ok(Some(CountingProblem::Models)));
+        assert_eq!(parse("c t wmc\nc p weight 1 0.5 0\np cnf 2 0\n"),
+            Ok(Some(CountingProblem::WeightedModels)));
+        assert_eq!(parse("c t pmc\nc p show 2 1 0\nc p show 2 0\np cnf 3 0\n"),
+            Ok(Some(CountingProblem::ProjectedModels(vec![Var(0), Var(1)]))));
+        assert_eq!(parse("c a comment\np cnf 2 1\n1 2 0\n"), Ok(None));
+        assert!(parse("c t mc\nc t pmc\np cnf 2 0\n").is_err());
+        assert!(parse("c t pmc\nc p show 3 0\np cnf 2 0\n").is_err());
+        assert!(parse("c t nope\np cnf 2 0\n").is_err());
+        assert!(parse("c t mc\n").is_err());
+    }
+}
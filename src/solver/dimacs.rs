@@ -0,0 +1,246 @@
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::str;
+use super::clause_index::ClauseIndex;
+use super::{ClauseDb, Formula, Literal, Var};
+
+/// Options for parsing DIMACS, see `Formula::parse_dimacs_str_with`. Start from the
+/// defaults and change them with the builder methods
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct ParseOptions {
+    /// Whether clauses may use variables above the number declared by the problem line,
+    /// which then adds variables up to the highest one used, instead of being an error
+    pub extend_vars: bool,
+    /// Whether to leave out clauses which repeat an earlier clause, with the literals in
+    /// any order, or which are tautologies, counting them in `Stats::duplicate_clauses`.
+    /// This renumbers the clauses after the first one left out
+    pub dedup: bool,
+}
+
+impl ParseOptions {
+    pub fn extend_vars(mut self, extend_vars: bool) -> Self {
+        self.extend_vars = extend_vars;
+        self
+    }
+
+    pub fn dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+}
+
+/// A counting problem declared by a `c t` comment of a DIMACS file, following the
+/// conventions of the model counting competition, see `Formula::parse_counting_problem_str`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CountingProblem {
+    /// `c t mc`, the number of models
+    Models,
+    /// `c t wmc`, the total weight of the models, with the weights of the literals given
+    /// by `c p weight` comments
+    WeightedModels,
+    /// `c t pmc`, the number of assignments to the variables listed by `c p show`
+    /// comments which extend to models
+    ProjectedModels(Vec<Var>),
+}
+
+// Reads DIMACS input straight from its bytes, without allocating for lines or tokens,
+// since parsing takes longer than solving on large easy instances
+struct Scanner<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    // The next line, without its line break, or None at the end of the input
+    fn line(&mut self) -> Option<&'a [u8]> {
+        let rest = &self.input[self.pos..];
+        if rest.is_empty() {
+            return None;
+        }
+        let len = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+        self.pos += (len + 1).min(rest.len());
+        let line = &rest[..len];
+        Some(line.strip_suffix(b"\r").unwrap_or(line))
+    }
+
+    // Skips whitespace, returning whether any input is left after it
+    fn skip_whitespace(&mut self) -> bool {
+        while self.pos < self.input.len() && self.input[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        self.pos < self.input.len()
+    }
+
+    // Reads the integer at the position, which must be followed by whitespace or the end
+    // of the input, and returns whether it is negative along with its magnitude
+    fn integer(&mut self) -> Result<(bool, usize), String> {
+        let start = self.pos;
+        let negative = self.input[self.pos] == b'-';
+        if negative || self.input[self.pos] == b'+' {
+            self.pos += 1;
+        }
+        let digits = self.pos;
+        let mut magnitude: usize = 0;
+        while let Some(&b) = self.input.get(self.pos) {
+            if !b.is_ascii_digit() {
+                break;
+            }
+            magnitude = magnitude.saturating_mul(10).saturating_add((b - b'0') as usize);
+            self.pos += 1;
+        }
+        let ended = self.input.get(self.pos).is_none_or(|b| b.is_ascii_whitespace());
+        if self.pos == digits || !ended || magnitude > isize::MAX as usize {
+            return Err(format!("Illegal variable '{}'", self.token(start)));
+        }
+        Ok((negative, magnitude))
+    }
+
+    // The token starting at the position, for error messages
+    fn token(&mut self, start: usize) -> String {
+        while self.pos < self.input.len() && !self.input[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        String::from_utf8_lossy(&self.input[start..self.pos]).into_owned()
+    }
+}
+
+impl Formula {
+    /// Reads the counting problem which the comments before the problem line of DIMACS
+    /// text declare, if any. A `c t mc`, `c t wmc` or `c t pmc` comment gives the kind
+    /// of count, and for `pmc` the `c p show` comments, each ending at a 0, list the
+    /// variables to project onto. Only the comments and the problem line are read, so
+    /// the text may end there
+    pub fn parse_counting_problem_str(input: &str) -> Result<Option<CountingProblem>, String> {
+        let mut kind = None;
+        let mut shown = vec![];
+        let mut num_vars = None;
+        for line in input.lines() {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("c") => (),
+                Some("p") => {
+                    num_vars = tokens.nth(1).and_then(|n| n.parse::<usize>().ok());
+                    break;
+                }
+                _ if line.starts_with('c') => continue,
+                _ => break,
+            }
+            match (tokens.next(), tokens.next()) {
+                (Some("t"), Some(tag)) => {
+                    if kind.is_some() {
+                        return Err("More than one problem type".to_owned());
+                    }
+                    kind = Some(tag.to_owned());
+                }
+                (Some("p"), Some("show")) => {
+                    for token in tokens.take_while(|&t| t != "0") {
+                        match token.parse::<usize>() {
+                            Ok(var) if var > 0 => shown.push(var),
+                            _ => return Err(format!("Illegal variable '{}'", token)),
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        let num_vars = num_vars.ok_or("Invalid/Missing problem line")?;
+        if let Some(&var) = shown.iter().find(|&&var| var > num_vars) {
+            return Err(format!("Variable {} is above the {} variables declared", var, num_vars));
+        }
+        match kind.as_deref() {
+            None => Ok(None),
+            Some("mc") => Ok(Some(CountingProblem::Models)),
+            Some("wmc") => Ok(Some(CountingProblem::WeightedModels)),
+            Some("pmc") => {
+                shown.sort_unstable();
+                shown.dedup();
+                Ok(Some(CountingProblem::ProjectedModels(
+                    shown.into_iter().map(|var| Var(var - 1)).collect())))
+            }
+            Some(tag) => Err(format!("Unknown problem type '{}'", tag)),
+        }
+    }
+
+    // Parses DIMACS input into the number of variables, the clauses and the number of
+    // clauses left out as duplicates
+    pub(super) fn parse_dimacs_clauses(input: &[u8], options: ParseOptions)
+        -> Result<(usize, ClauseDb, u64), String> {
+        let mut scanner = Scanner { input, pos: 0 };
+        // Parse comments and problem line
+        let problem_line = loop {
+            let line = scanner.line().ok_or("Missing problem line")?;
+            if !line.starts_with(b"c") {
+                break line;
+            }
+        };
+
+        let mut num_vars: usize;
+        let num_clauses: usize;
+        let problem_line = str::from_utf8(problem_line).unwrap_or("");
+        let params: Vec<_> = problem_line.split_whitespace().collect();
+        if params.len() != 4 || params[0] != "p" {
+            return Err("Invalid/Missing problem line".to_owned());
+        } else if params[1] != "cnf" {
+            return Err("Only cnf-formatted inputs are currently supported".to_owned());
+        } else {
+            num_vars = params[2].parse()
+                .map_err(|_| "Third problem line parameter invalid".to_owned())?;
+            num_clauses = params[3].parse()
+                .map_err(|_| "Fourth problem line parameter invalid".to_owned())?;
+            check_num_vars(num_vars)?;
+        }
+
+        // Parse the clauses, each of which ends at a 0
+        let mut clauses = ClauseDb::new();
+        let mut clause = vec![];
+        let (mut num_read, mut duplicates) = (0, 0);
+        let mut index = ClauseIndex::default();
+        let mut normalized = vec![];
+        while scanner.skip_whitespace() {
+            if num_read == num_clauses {
+                return Err("Too many clauses".to_owned());
+            }
+            match scanner.integer()? {
+                (_, 0) => {
+                    num_read += 1;
+                    if options.dedup {
+                        normalized.clone_from(&clause);
+                        normalized.sort_unstable_by_key(|l: &Literal| l.index());
+                        normalized.dedup();
+                        if !index.insert(&normalized, clauses.len(), &clauses) {
+                            duplicates += 1;
+                            clause.clear();
+                            continue;
+                        }
+                    }
+                    clauses.push(clause.drain(..));
+                }
+                (negated, var) => {
+                    if var > num_vars {
+                        if !options.extend_vars {
+                            return Err(format!("Variable {} is above the {} variables declared",
+                                var, num_vars));
+                        }
+                        check_num_vars(var)?;
+                        num_vars = var;
+                    }
+                    clause.push(Literal::new(var - 1, negated));
+                }
+            }
+        }
+        if num_read < num_clauses {
+            return Err("Not enough clauses".to_owned());
+        }
+        Ok((num_vars, clauses, duplicates))
+    }
+}
+
+fn check_num_vars(num_vars: usize) -> Result<(), String> {
+    if num_vars > Literal::MAX_ID + 1 {
+        return Err(format!("At most {} variables are supported", Literal::MAX_ID + 1));
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::heuristic::DecisionHeuristic;
+use super::{Assignment, Formula, Literal, SolveResult, Var};
+
+/// An iterator over the models of a formula, see `Formula::iter_models` and
+/// `Formula::iter_projected_models`
+pub struct Models<'a> {
+    // The formula along with a blocking clause for each model found so far
+    formula: Formula,
+    heuristic: &'a mut dyn DecisionHeuristic,
+    // Whether each variable is kept in the models, indexed by id
+    projection: Vec<bool>,
+    done: bool,
+}
+
+impl Iterator for Models<'_> {
+    type Item = Assignment;
+
+    fn next(&mut self) -> Option<Assignment> {
+        if self.done {
+            return None;
+        }
+        match self.formula.clone().solve(self.heuristic) {
+            SolveResult::Sat(mut model) => {
+                for (value, &kept) in model.0.iter_mut().zip(&self.projection) {
+                    if !kept {
+                        *value = None;
+                    }
+                }
+                // Blocks exactly the assignments to the kept variables which the model
+                // stands for
+                let blocking = model.0.iter().enumerate()
+                    .filter_map(|(id, negated)| negated.map(|n| Literal::new(id, !n)));
+                self.formula.clauses.push(blocking);
+                Some(model)
+            }
+            SolveResult::Unsat | SolveResult::Unknown => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+impl Formula {
+    /// Enumerates the models of the formula by solving it repeatedly, each time with a
+    /// clause blocking the previous models. A model leaves unassigned the variables
+    /// which can take either value, so each one stands for every way of assigning them.
+    /// No total assignment is covered by two models. The iteration ends early if the
+    /// search gives up
+    pub fn iter_models<'a>(&self, heuristic: &'a mut dyn DecisionHeuristic) -> Models<'a> {
+        let projection = vec![true; self.num_vars()];
+        Models { formula: self.clone(), heuristic, projection, done: false }
+    }
+
+    /// Like `iter_models`, but enumerates the assignments to the given variables which
+    /// extend to models, leaving the other variables unassigned. Only the given
+    /// variables appear in blocking clauses, so models which differ elsewhere, such as
+    /// in auxiliary variables, are not enumerated separately
+    pub fn iter_projected_models<'a>(&self, vars: &[Var], heuristic: &'a mut dyn DecisionHeuristic)
+        -> Models<'a> {
+        let mut projection = vec![false; self.num_vars()];
+        for var in vars {
+            assert!(var.0 < self.num_vars(), "Unknown variable {}", var);
+            projection[var.0] = true;
+        }
+        Models { formula: self.clone(), heuristic, projection, done: false }
+    }
+}
+
@@ -0,0 +1,79 @@
+use alloc::vec::Vec;
+use crate::heuristic::DecisionHeuristic;
+use super::{Assignment, Formula, SolveResult};
+
+/// How the models of two formulas relate, see `Formula::compare`
+pub enum Comparison {
+    /// Every assignment satisfies both formulas or neither
+    Equivalent,
+    /// The assignment satisfies the first formula but not the second
+    FirstOnly(Assignment),
+    /// The assignment satisfies the second formula but not the first
+    SecondOnly(Assignment),
+    /// The search gave up
+    Unknown,
+}
+
+impl Formula {
+    /// Solves for an assignment which satisfies the formula but not the other one, so
+    /// the result is Unsat exactly if the formula implies the other one. The other
+    /// formula is negated by a fresh variable per clause, which is true exactly if the
+    /// clause is falsified, along with a clause demanding that one of them is. Models
+    /// cover the variables of both formulas
+    pub fn difference(&self, other: &Formula, heuristic: &mut dyn DecisionHeuristic)
+        -> SolveResult {
+        let num_vars = self.num_vars().max(other.num_vars());
+        let mut combined = self.clone();
+        while combined.num_vars() < num_vars {
+            combined.new_var();
+        }
+        let falsified: Vec<_> = other.clauses()
+            .map(|clause| {
+                let falsified = combined.new_var().positive();
+                for &lit in clause {
+                    combined.add_clause([!falsified, !lit]);
+                }
+                combined.add_clause(clause.iter().copied().chain([falsified]));
+                falsified
+            })
+            .collect();
+        combined.add_clause(falsified);
+        match combined.solve(heuristic) {
+            SolveResult::Sat(mut model) => {
+                model.0.truncate(num_vars);
+                SolveResult::Sat(model)
+            }
+            result => result,
+        }
+    }
+
+    /// Decides whether the formulas are logically equivalent, i.e. have the same models,
+    /// by looking for a `difference` in each direction
+    pub fn compare(&self, other: &Formula, heuristic: &mut dyn DecisionHeuristic)
+        -> Comparison {
+        match self.difference(other, heuristic) {
+            SolveResult::Sat(model) => return Comparison::FirstOnly(model),
+            SolveResult::Unknown => return Comparison::Unknown,
+            SolveResult::Unsat => (),
+        }
+        match other.difference(self, heuristic) {
+            SolveResult::Sat(model) => Comparison::SecondOnly(model),
+            SolveResult::Unknown => Comparison::Unknown,
+            SolveResult::Unsat => Comparison::Equivalent,
+        }
+    }
+
+    /// Decides whether the formulas are both satisfiable or both unsatisfiable, or
+    /// returns None if the search gives up
+    pub fn equisatisfiable(&self, other: &Formula, heuristic: &mut dyn DecisionHeuristic)
+        -> Option<bool> {
+        let satisfiable = |formula: &Formula, heuristic: &mut dyn DecisionHeuristic| {
+            match formula.clone().solve(heuristic) {
+                SolveResult::Sat(_) => Some(true),
+                SolveResult::Unsat => Some(false),
+                SolveResult::Unknown => None,
+            }
+        };
+        Some(satisfiable(self, heuristic)? == satisfiable(other, heuristic)?)
+    }
+}
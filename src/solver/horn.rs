@@ -0,0 +1,140 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use super::{Assignment, Clause, ClauseDb, Formula, Literal};
+
+// Renamable Horn detection looks at every pair of literals within a clause,
+// so it's skipped on formulas where that would be too expensive
+const MAX_RENAMING_PAIRS: usize = 1 << 20;
+
+impl Formula {
+    // Returns the variables to flip to make every clause contain at most one positive
+    // literal, if possible. For a Horn formula that's no variables at all
+    pub(super) fn horn_renaming(&self) -> Option<Vec<bool>> {
+        let clauses: Vec<_> = self.clauses.iter().map(Clause::normalized).collect();
+        if clauses.iter().all(|c| c.iter().filter(|l| !l.is_negated()).count() <= 1) {
+            return Some(vec![false; self.num_vars()]);
+        }
+
+        let pairs: usize = clauses.iter().map(|c| c.len() * c.len().saturating_sub(1) / 2).sum();
+        if pairs > MAX_RENAMING_PAIRS {
+            return None;
+        }
+
+        // With the variable of each literal meaning "flip this variable", a literal
+        // ends up positive exactly when it's false, so no two literals of a clause
+        // may both end up positive exactly when the 2-SAT formula with the clause
+        // (a, b) for each pair of literals a and b of a clause is satisfied
+        let mut renaming = ClauseDb::new();
+        for clause in &clauses {
+            for (i, &a) in clause.iter().enumerate() {
+                for &b in &clause[i + 1..] {
+                    renaming.push([a, b]);
+                }
+            }
+        }
+        let flips = self.with_clauses(renaming).solve_two_sat()?;
+        Some(flips.0.iter().map(|&negated| negated == Some(false)).collect())
+    }
+
+    // Solves the formula after flipping the given variables, which must make it Horn.
+    // Starting from every variable being false, the only way to satisfy a clause whose
+    // negative literals are all false is to make its positive literal true, which in
+    // turn can only falsify more negative literals. The formula is unsatisfiable
+    // exactly when this process falsifies a clause without a positive literal
+    pub(super) fn solve_horn(&self, flips: &[bool]) -> Option<Assignment> {
+        let rename = |lit: Literal| if flips[lit.id()] { !lit } else { lit };
+        let clauses: Vec<Vec<_>> = self.clauses.iter()
+            .map(|c| c.normalized().into_iter().map(rename).collect())
+            .collect();
+
+        let mut values = vec![false; self.num_vars()];
+        let mut remaining = vec![0; clauses.len()];
+        let mut negative_occurrences = vec![vec![]; self.num_vars()];
+        let mut queue = vec![];
+        for (i, clause) in clauses.iter().enumerate() {
+            for lit in clause.iter().filter(|l| l.is_negated()) {
+                remaining[i] += 1;
+                negative_occurrences[lit.id()].push(i);
+            }
+            if remaining[i] == 0 {
+                queue.push(i);
+            }
+        }
+
+        while let Some(i) = queue.pop() {
+            let positive = clauses[i].iter().find(|l| !l.is_negated())?;
+            if values[positive.id()] {
+                continue;
+            }
+            values[positive.id()] = true;
+            for &j in &negative_occurrences[positive.id()] {
+                remaining[j] -= 1;
+                if remaining[j] == 0 {
+                    queue.push(j);
+                }
+            }
+        }
+
+        let mut assignment = Assignment::new(self.num_vars());
+        for (id, &value) in values.iter().enumerate() {
+            assignment.assign(rename(Literal::new(id, !value)));
+        }
+        Some(assignment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::Rng;
+    use crate::solver::testing;
+    use crate::solver::{Clause, Formula};
+
+    // Whether every clause has at most one positive literal after flipping the variables
+    fn is_horn_after(formula: &Formula, flips: &[bool]) -> bool {
+        formula.clauses().all(|c| {
+            c.normalized().iter().filter(|l| l.is_negated() == flips[l.id()]).count() <= 1
+        })
+    }
+
+    #[test]
+    fn solves_small_instances() {
+        let sat = testing::formula("p cnf 3 4\n1 0\n-1 2 0\n-1 -2 3 0\n-3 -2 1 0\n");
+        let flips = sat.horn_renaming().expect("Horn");
+        assert!(flips.iter().all(|&flip| !flip));
+        let model = sat.solve_horn(&flips).expect("Satisfiable");
+        assert!(testing::is_model(&sat, &model));
+        let unsat = testing::formula("p cnf 2 3\n1 0\n-1 2 0\n-2 -1 0\n");
+        assert!(unsat.solve_horn(&unsat.horn_renaming().expect("Horn")).is_none());
+        // Flipping 1 turns it into a Horn formula
+        let renamable = testing::formula("p cnf 3 2\n1 2 -3 0\n1 3 0\n");
+        let flips = renamable.horn_renaming().expect("Renamable");
+        assert!(is_horn_after(&renamable, &flips));
+        assert!(testing::formula("p cnf 3 2\n1 2 3 0\n-1 -2 -3 0\n").horn_renaming().is_none());
+    }
+
+    #[test]
+    fn agrees_with_enumeration() {
+        let mut rng = Rng::new(5);
+        let (mut renamable, mut satisfiable) = (0, 0);
+        for _ in 0..400 {
+            let formula = testing::random_formula(&mut rng, 6, 6, 3);
+            let num_vars = formula.num_vars();
+            match formula.horn_renaming() {
+                Some(flips) => {
+                    renamable += 1;
+                    assert!(is_horn_after(&formula, &flips));
+                    match formula.solve_horn(&flips) {
+                        Some(model) => {
+                            satisfiable += 1;
+                            assert!(testing::is_model(&formula, &model));
+                        }
+                        None => assert!(!testing::satisfiable(&formula)),
+                    }
+                }
+                None => assert!((0..1 << num_vars)
+                    .all(|bits| !is_horn_after(&formula, &testing::values(num_vars, bits)))),
+            }
+        }
+        assert!(renamable > 40 && satisfiable > 20 && satisfiable < renamable);
+    }
+}
@@ -0,0 +1,231 @@
+use core::sync::atomic::AtomicBool;
+use core::time::Duration;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::config::SolverConfig;
+use crate::heuristic::DecisionHeuristic;
+use crate::rng;
+use super::clause_index::ClauseIndex;
+use super::{Assignment, Clause, CoreResult, Engine, Formula, Literal, LogCallback, LogLevel,
+    ProgressCallback, SolveResult, Stats, Var};
+
+/// A formula which can be solved repeatedly, possibly under assumptions, while clauses
+/// are added in between. Later solves only search again if the previous model breaks
+/// a new clause or an assumption, and once the clauses are unsatisfiable by themselves
+/// they stay so without searching
+pub struct Solver {
+    formula: Formula,
+    heuristic: Box<dyn DecisionHeuristic>,
+    model: Option<Assignment>,
+    unsat: bool,
+    // The failed assumptions of the last solve, if it was unsatisfiable
+    failed: Vec<Literal>,
+    // Clauses before this index are known to be satisfied by the model
+    checked: usize,
+    stats: Stats,
+    // Preprocessing to run on a copy of the formula before each search
+    config: Option<SolverConfig>,
+    // The clauses so far, against which added clauses are checked for duplicates
+    index: ClauseIndex,
+    // The seed of the last search, if each search gets a fresh one, see set_reseeding
+    seed: Option<u64>,
+}
+
+impl Solver {
+    /// Creates a solver for the formula which branches with the given heuristic
+    pub fn new(formula: Formula, heuristic: Box<dyn DecisionHeuristic>) -> Self {
+        let mut index = ClauseIndex::default();
+        for (i, clause) in formula.clauses.iter().enumerate() {
+            index.insert(&clause.normalized(), i, &formula.clauses);
+        }
+        Solver {
+            stats: Stats { duplicate_clauses: formula.stats.duplicate_clauses, ..Stats::default() },
+            formula,
+            heuristic,
+            model: None,
+            unsat: false,
+            failed: vec![],
+            checked: 0,
+            config: None,
+            index,
+            seed: None,
+        }
+    }
+
+    /// Creates a solver for the formula which searches and preprocesses as configured
+    pub fn with_config(mut formula: Formula, config: SolverConfig) -> Self {
+        formula.configure(&config);
+        Solver { config: Some(config), ..Solver::new(formula, config.heuristic.build()) }
+    }
+
+    /// The clauses added so far
+    pub fn formula(&self) -> &Formula {
+        &self.formula
+    }
+
+    /// Adds a fresh variable, see `Formula::new_var`
+    pub fn new_var(&mut self) -> Var {
+        if let Some(model) = &mut self.model {
+            model.0.push(None);
+        }
+        self.formula.new_var()
+    }
+
+    /// Adds a clause, see `Formula::add_clause`, unless it repeats an earlier clause, with
+    /// the literals in any order, or is a tautology. Those are left out, since they
+    /// change nothing, and counted in `Stats::duplicate_clauses`
+    pub fn add_clause(&mut self, literals: impl IntoIterator<Item = Literal>) {
+        let clause: Vec<_> = literals.into_iter().collect();
+        let num_vars = self.formula.num_vars();
+        for lit in &clause {
+            assert!(lit.id() < num_vars, "Literal over unknown variable {}", lit.to_var());
+        }
+        let clauses = &self.formula.clauses;
+        if self.index.insert(&clause.normalized(), clauses.len(), clauses) {
+            self.formula.add_clause(clause);
+        } else {
+            self.stats.duplicate_clauses += 1;
+        }
+    }
+
+    /// Keeps the variable out of the preprocessing before each solve, see `Formula::freeze`
+    pub fn freeze(&mut self, var: Var) {
+        self.formula.freeze(var);
+    }
+
+    /// Undoes `freeze`, see `Formula::melt`
+    pub fn melt(&mut self, var: Var) {
+        self.formula.melt(var);
+    }
+
+    /// Makes later solves give up with Unknown, see `Formula::set_conflict_limit`
+    pub fn set_conflict_limit(&mut self, limit: Option<u64>) {
+        self.formula.set_conflict_limit(limit);
+    }
+
+    /// Makes later solves give up with Unknown, see `Formula::set_propagation_limit`
+    pub fn set_propagation_limit(&mut self, limit: Option<u64>) {
+        self.formula.set_propagation_limit(limit);
+    }
+
+    /// Makes later solves give up with Unknown, see `Formula::set_time_limit`
+    pub fn set_time_limit(&mut self, limit: Option<Duration>) {
+        self.formula.set_time_limit(limit);
+    }
+
+    /// Makes later solves give up with Unknown once the flag is set, see
+    /// `Formula::set_interrupt`
+    pub fn set_interrupt(&mut self, interrupt: Option<Arc<AtomicBool>>) {
+        self.formula.set_interrupt(interrupt);
+    }
+
+    /// Reports the progress of later solves, see `Formula::set_progress`
+    pub fn set_progress(&mut self, progress: Option<(u64, ProgressCallback)>) {
+        self.formula.set_progress(progress);
+    }
+
+    /// Logs the events of later solves, see `Formula::set_log`
+    pub fn set_log(&mut self, log: Option<(LogLevel, LogCallback)>) {
+        self.formula.set_log(log);
+    }
+
+    /// Warm-starts later solves, e.g. from the previous model, see `Formula::set_hint`
+    pub fn set_hint(&mut self, hint: Option<Assignment>) {
+        self.formula.set_hint(hint);
+    }
+
+    /// Gives each later search a fresh seed, derived from the given one and the
+    /// searches before it, so that similar queries in a row don't all take the same
+    /// unlucky path. The seed starts WalkSAT, see `Engine::WalkSat`, and perturbs the
+    /// clause order and tie-breaking of DPLL, see `Formula::set_chaos_seed`. The seeds
+    /// only change how the answer is found, and the same seed gives the same searches.
+    /// None keeps the seeds of the formula
+    pub fn set_reseeding(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    /// Solves the clauses added so far
+    pub fn solve(&mut self) -> SolveResult {
+        self.solve_under(&[])
+    }
+
+    /// Solves the clauses added so far with the assumed literals forced to be true.
+    /// The assumptions only hold for this solve. The limits apply to each call as a
+    /// whole, including finding the failed assumptions, so a call which gives up
+    /// before those are found answers Unknown
+    pub fn solve_under(&mut self, assumptions: &[Literal]) -> SolveResult {
+        self.failed.clear();
+        if self.unsat {
+            return SolveResult::Unsat;
+        }
+        if let Some(model) = &self.model {
+            if self.formula.clauses.iter().skip(self.checked).all(|c| c.solved(model))
+                && assumptions.iter().all(|&l| model.assigned(l)) {
+                self.checked = self.formula.clauses.len();
+                return SolveResult::Sat(model.clone());
+            }
+        }
+
+        self.model = None;
+        let mut formula = self.formula.restricted(assumptions);
+        if let Some(config) = &self.config {
+            formula.preprocess(config);
+        }
+        if let Some(seed) = &mut self.seed {
+            *seed = rng::mix(*seed);
+            match &mut formula.engine {
+                Engine::WalkSat { seed: walk_sat_seed, .. } => *walk_sat_seed = *seed,
+                _ => formula.set_chaos_seed(Some(*seed)),
+            }
+        }
+        // Finding the failed assumptions keeps to the limits of the call too
+        let mut budget = self.formula.budget.clone();
+        let (result, stats) = formula.solve_sharing(&mut budget, self.heuristic.as_mut());
+        self.stats.decisions += stats.decisions;
+        self.stats.conflicts += stats.conflicts;
+        self.stats.propagations += stats.propagations;
+        self.stats.peak_memory_kb = stats.peak_memory_kb;
+        match &result {
+            SolveResult::Sat(model) => {
+                self.model = Some(model.clone());
+                self.checked = self.formula.clauses.len();
+            }
+            SolveResult::Unsat => {
+                let heuristic = self.heuristic.as_mut();
+                match self.formula.failed_assumptions_sharing(assumptions, heuristic, &mut budget) {
+                    CoreResult::Minimal(failed) => {
+                        self.unsat = failed.is_empty();
+                        self.failed = failed;
+                    }
+                    CoreResult::Sat => {
+                        unreachable!("The search found the assumptions unsatisfiable")
+                    }
+                    // Without the failed assumptions the answer is incomplete, and the clauses
+                    // may still be satisfiable without the assumptions
+                    CoreResult::Unknown => return SolveResult::Unknown,
+                }
+            }
+            SolveResult::Unknown => (),
+        }
+        result
+    }
+
+    /// A minimal set of the assumptions of the last solve which are unsatisfiable
+    /// together with the clauses. Empty if the last solve was satisfiable, or if the
+    /// clauses are unsatisfiable by themselves
+    pub fn failed(&self) -> &[Literal] {
+        &self.failed
+    }
+
+    /// Counters for the work done by all solves so far
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// The model found by the last solve, if it found one
+    pub fn model(&self) -> Option<&Assignment> {
+        self.model.as_ref()
+    }
+}
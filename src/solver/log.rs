@@ -0,0 +1,106 @@
+use core::str::FromStr;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use super::{Formula, Literal, SolveResult};
+
+/// How much of the search `Formula::set_log` reports, each level including the ones
+/// before it
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum LogLevel {
+    /// Nothing
+    Quiet,
+    /// The start of the search, with the size of the formula and the algorithm used,
+    /// and its result with the counters
+    Normal,
+    /// Every conflict, with the falsified clause, and every clause added by an
+    /// external propagator
+    Verbose,
+    /// Every decision, along with its decision level
+    Debug,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "quiet" => Ok(LogLevel::Quiet),
+            "normal" => Ok(LogLevel::Normal),
+            "verbose" => Ok(LogLevel::Verbose),
+            "debug" => Ok(LogLevel::Debug),
+            _ => Err(format!("Unknown log level '{}'", s)),
+        }
+    }
+}
+
+/// Receives the lines logged by the search, see `Formula::set_log`
+pub type LogCallback = Arc<dyn Fn(&str) + Send + Sync>;
+
+impl Formula {
+    /// Makes the search call the callback with a line for each event of the given level
+    /// or below, e.g. to watch it work on small formulas
+    pub fn set_log(&mut self, log: Option<(LogLevel, LogCallback)>) {
+        self.log = log;
+    }
+
+    // Logs the message if the level is enabled, only formatting it then
+    pub(super) fn log(&self, level: LogLevel, message: impl FnOnce() -> String) {
+        if let Some((enabled, callback)) = &self.log {
+            if level <= *enabled {
+                callback(&message());
+            }
+        }
+    }
+
+    // Logs the start of the search with the given algorithm
+    pub(super) fn log_start(&self, algorithm: &str) {
+        self.log(LogLevel::Normal, || {
+            format!("solving {} variables and {} clauses with {}", self.num_vars(),
+                self.clauses.len(), algorithm)
+        });
+    }
+
+    // Logs the decision, which has just been assigned
+    pub(super) fn log_decision(&self, lit: Literal) {
+        self.log(LogLevel::Debug, || format!("decide {} at level {}", lit, self.level()));
+    }
+
+    // Logs a conflict in the clause
+    pub(super) fn log_conflict(&self, clause: &[Literal]) {
+        self.log(LogLevel::Verbose, || {
+            format!("conflict at level {} in {}", self.level(), clause_text(clause))
+        });
+    }
+
+    // Logs a clause added by an external propagator
+    pub(super) fn log_external(&self, clause: &[Literal]) {
+        self.log(LogLevel::Verbose, || format!("external clause {}", clause_text(clause)));
+    }
+
+    // Logs the result of the search along with the counters
+    pub(super) fn log_result(&self, result: &SolveResult) {
+        self.log(LogLevel::Normal, || {
+            let answer = match result {
+                SolveResult::Sat(_) => "SATISFIABLE",
+                SolveResult::Unsat => "UNSATISFIABLE",
+                SolveResult::Unknown => "UNKNOWN",
+            };
+            format!("{} after {} decisions and {} conflicts", answer, self.stats.decisions,
+                self.stats.conflicts)
+        });
+    }
+
+    // The decision level, which is the number of assigned variables, since every
+    // assignment of the search is a decision
+    fn level(&self) -> usize {
+        self.assignment.0.iter().filter(|v| v.is_some()).count()
+    }
+}
+
+// The clause as DIMACS literals
+fn clause_text(clause: &[Literal]) -> String {
+    let mut text: String = clause.iter().map(|lit| format!("{} ", lit)).collect();
+    text.push('0');
+    text
+}
@@ -0,0 +1,164 @@
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::Read;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::heuristic::DecisionHeuristic;
+use super::{Assignment, Clause, Formula, Literal, SolveResult};
+
+impl Formula {
+    /// Splits the formula into cubes, i.e. partial assignments given as lists of literals,
+    /// by branching up to `depth` times on the variable picked by lookahead. The formula
+    /// is satisfiable exactly if it's satisfiable together with one of the cubes.
+    /// Cubes refuted by unit propagation are left out
+    pub fn lookahead_cubes(&self, depth: usize) -> Vec<Vec<Literal>> {
+        let mut cubes = vec![];
+        let mut root = Assignment::new(self.num_vars());
+        if self.propagate(&mut root) {
+            self.extend_cube(&mut vec![], &root, depth, &mut cubes);
+        }
+        cubes
+    }
+
+    // Adds the cubes extending the given one, whose propagated assignment is `fixed`
+    fn extend_cube(&self, cube: &mut Vec<Literal>, fixed: &Assignment, depth: usize,
+                   cubes: &mut Vec<Vec<Literal>>) {
+        let open: Vec<_> = self.clauses.iter().filter(|c| !c.solved(fixed)).collect();
+        if depth == 0 || open.is_empty() {
+            cubes.push(cube.clone());
+            return;
+        }
+
+        let mut candidates: Vec<_> = open.iter()
+            .flat_map(|c| c.iter().map(|l| l.id()))
+            .filter(|&id| fixed.0[id].is_none())
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        // Picks the variable whose two branches both shorten many clauses
+        let mut best = None;
+        for id in candidates {
+            let pos = Literal::new(id, false);
+            let (pos_branch, neg_branch) =
+                match (self.look_ahead(fixed, pos), self.look_ahead(fixed, !pos)) {
+                    (Some(pos_branch), Some(neg_branch)) => (pos_branch, neg_branch),
+                    (None, None) => return,
+                    // A literal failing to propagate leaves only its negation
+                    (pos_branch, neg_branch) => {
+                        let (lit, (forced, _)) = match pos_branch {
+                            Some(branch) => (pos, branch),
+                            None => (!pos, neg_branch.unwrap()),
+                        };
+                        cube.push(lit);
+                        self.extend_cube(cube, &forced, depth, cubes);
+                        cube.pop();
+                        return;
+                    }
+                };
+            let score = (pos_branch.1 * neg_branch.1, pos_branch.1 + neg_branch.1);
+            if best.as_ref().is_none_or(|b: &(_, _, _, _)| score > b.0) {
+                best = Some((score, pos, pos_branch.0, neg_branch.0));
+            }
+        }
+
+        if let Some((_, pos, pos_fixed, neg_fixed)) = best {
+            for (lit, branch) in [(pos, pos_fixed), (!pos, neg_fixed)] {
+                cube.push(lit);
+                self.extend_cube(cube, &branch, depth - 1, cubes);
+                cube.pop();
+            }
+        }
+    }
+
+    // Propagates the literal on top of the assignment. Returns the extended assignment
+    // along with the number of clauses it shortens without satisfying,
+    // or None if propagation falsifies a clause
+    fn look_ahead(&self, fixed: &Assignment, lit: Literal) -> Option<(Assignment, usize)> {
+        let mut extended = fixed.clone();
+        extended.assign(lit);
+        if !self.propagate(&mut extended) {
+            return None;
+        }
+        let open = |c: &[Literal], a: &Assignment| {
+            c.iter().filter(|l| a.0[l.id()].is_none()).count()
+        };
+        let shortened = self.clauses.iter()
+            .filter(|c| !c.solved(&extended) && open(c, &extended) < open(c, fixed))
+            .count();
+        Some((extended, shortened))
+    }
+
+    /// Parses the cubes of an iCNF file, i.e. its lines of the form `a <literals> 0`
+    #[cfg(feature = "std")]
+    pub fn parse_cubes(&self, mut file: File) -> Result<Vec<Vec<Literal>>, String> {
+        let mut buf = String::new();
+        file.read_to_string(&mut buf).map_err(|_| "Error while reading file")?;
+        self.parse_cubes_str(&buf)
+    }
+
+    /// Parses the cubes of iCNF text, see `parse_cubes`
+    pub fn parse_cubes_str(&self, input: &str) -> Result<Vec<Vec<Literal>>, String> {
+        let mut cubes = vec![];
+        for line in input.lines().filter(|l| l.starts_with("a ")) {
+            let mut cube = vec![];
+            for v in line[2..].split_whitespace().take_while(|&v| v != "0") {
+                let v: isize = v.parse().map_err(|_| format!("Illegal variable '{}'", v))?;
+                if v == 0 || v.unsigned_abs() > self.num_vars() {
+                    return Err(format!("Variable '{}' is out of range", v));
+                }
+                cube.push(Literal::from_var(v));
+            }
+            cubes.push(cube);
+        }
+        Ok(cubes)
+    }
+
+    /// Solves the formula together with each cube in turn, stopping at the first model.
+    /// Only gives an answer for the formula itself if the cubes cover every assignment,
+    /// like those from `lookahead_cubes`. The limits of the formula apply to all the
+    /// searches together, so the time limit is for the whole run rather than each cube
+    pub fn solve_cubes(&self, cubes: &[Vec<Literal>], heuristic: &mut dyn DecisionHeuristic)
+        -> SolveResult {
+        let mut budget = self.budget.clone();
+        let mut result = SolveResult::Unsat;
+        for cube in cubes {
+            match self.restricted(cube).solve_sharing(&mut budget, heuristic).0 {
+                SolveResult::Sat(model) => return SolveResult::Sat(model),
+                SolveResult::Unsat => (),
+                SolveResult::Unknown => result = SolveResult::Unknown,
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use crate::generate;
+    use crate::heuristic::Heuristic;
+    use crate::solver::{Literal, SolveResult};
+
+    #[test]
+    fn cubes_share_the_limits() {
+        let heuristic = || Heuristic::Ordered.build();
+        let formula = generate::pigeonhole(5);
+        let lit = Literal::new(0, false);
+        let cubes = vec![vec![lit], vec![!lit]];
+        assert!(matches!(formula.solve_cubes(&cubes, heuristic().as_mut()), SolveResult::Unsat));
+
+        // Each cube on its own is refuted within the limit, but not both together
+        let conflicts = cubes.iter()
+            .map(|cube| formula.restricted(cube).solve_with_stats(heuristic().as_mut()).1)
+            .map(|stats| stats.conflicts)
+            .max()
+            .unwrap();
+        let mut limited = formula.clone();
+        limited.set_conflict_limit(Some(conflicts + 1));
+        assert!(matches!(limited.solve_cubes(&cubes, heuristic().as_mut()), SolveResult::Unknown));
+    }
+}
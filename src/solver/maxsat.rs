@@ -0,0 +1,454 @@
+use core::str::FromStr;
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::Read;
+use crate::heuristic::{DecisionHeuristic, Phase};
+use super::{Assignment, CardinalityEncoding, Formula, Literal, SolveResult, TraceResult};
+use super::cardinality::at_most_weight;
+
+/// Hard clauses, which must be satisfied, along with weighted soft clauses, whose
+/// falsified weight is to be minimized
+#[derive(Clone)]
+pub struct WeightedFormula {
+    hard: Formula,
+    soft: Vec<(u64, Vec<Literal>)>,
+}
+
+/// The outcome of solving a weighted formula
+pub enum MaxSatResult {
+    /// An assignment of least cost, which assigns every variable, along with its cost
+    Optimum(Assignment, u64),
+    /// The limits of the hard clauses ran out, and this is the cheapest assignment
+    /// found until then, along with its cost
+    Best(Assignment, u64),
+    /// The hard clauses are unsatisfiable
+    Unsat,
+    /// The limits of the hard clauses ran out before any assignment satisfying them
+    /// was found
+    Unknown,
+}
+
+/// The algorithms for solving weighted formulas
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MaxSatAlgorithm {
+    /// See `WeightedFormula::solve_maxsat`
+    CoreGuided,
+    /// See `WeightedFormula::solve_maxsat_linear`
+    Linear,
+}
+
+impl FromStr for MaxSatAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "core" => Ok(MaxSatAlgorithm::CoreGuided),
+            "linear" => Ok(MaxSatAlgorithm::Linear),
+            _ => Err(format!("Unknown MaxSAT algorithm '{}'", s)),
+        }
+    }
+}
+
+impl WeightedFormula {
+    /// Creates a weighted formula with the given hard clauses and no soft clauses
+    pub fn new(hard: Formula) -> Self {
+        WeightedFormula { hard, soft: vec![] }
+    }
+
+    /// The hard clauses, to which variables are added
+    pub fn hard(&mut self) -> &mut Formula {
+        &mut self.hard
+    }
+
+    /// Adds a soft clause over variables of the hard formula. Clauses of weight 0 are
+    /// ignored, since falsifying them costs nothing
+    pub fn add_soft_clause(&mut self, weight: u64, literals: impl IntoIterator<Item = Literal>) {
+        let clause: Vec<_> = literals.into_iter().collect();
+        for lit in &clause {
+            assert!(lit.id() < self.hard.num_vars(), "Literal over unknown variable {}", lit);
+        }
+        if weight > 0 {
+            self.soft.push((weight, clause));
+        }
+    }
+
+    /// Iterates over the soft clauses along with their weights
+    pub fn soft_clauses(&self) -> impl Iterator<Item = (u64, &[Literal])> {
+        self.soft.iter().map(|(weight, clause)| (*weight, &clause[..]))
+    }
+
+    /// Parses a WCNF file, see `parse_wcnf_str`
+    #[cfg(feature = "std")]
+    pub fn parse_wcnf(mut file: File) -> Result<WeightedFormula, String> {
+        let mut buf = String::new();
+        file.read_to_string(&mut buf).map_err(|_| "Error while reading file")?;
+        WeightedFormula::parse_wcnf_str(&buf)
+    }
+
+    /// Parses WCNF text, either in the classic format with a `p wcnf <vars> <clauses>
+    /// <top>` line, where clauses of weight at least `top` are hard, or in the format
+    /// without a problem line, where hard clauses start with `h`
+    pub fn parse_wcnf_str(input: &str) -> Result<WeightedFormula, String> {
+        let mut lines = input.lines()
+            .filter(|l| !l.starts_with('c') && !l.trim().is_empty())
+            .peekable();
+        let mut header = None;
+        let mut top = None;
+        if let Some(line) = lines.next_if(|l| l.starts_with('p')) {
+            let params: Vec<_> = line.split_whitespace().collect();
+            if !(4..=5).contains(&params.len()) || params[0] != "p" || params[1] != "wcnf" {
+                return Err("Invalid problem line".to_owned());
+            }
+            let num_vars = params[2].parse()
+                .map_err(|_| "Third problem line parameter invalid".to_owned())?;
+            let num_clauses = params[3].parse()
+                .map_err(|_| "Fourth problem line parameter invalid".to_owned())?;
+            header = Some((num_vars, num_clauses));
+            if let Some(top_weight) = params.get(4) {
+                let top_weight: u64 = top_weight.parse()
+                    .map_err(|_| "Fifth problem line parameter invalid".to_owned())?;
+                top = Some(top_weight);
+            }
+        }
+        let mut tokens = lines.flat_map(str::split_whitespace);
+
+        let mut hard = vec![];
+        let mut soft = vec![];
+        while let Some(weight) = tokens.next() {
+            let weight = match weight {
+                "h" if header.is_none() => None,
+                _ => {
+                    let weight: u64 = weight.parse()
+                        .map_err(|_| format!("Illegal weight '{}'", weight))?;
+                    Some(weight).filter(|&w| top.is_none_or(|top| w < top))
+                }
+            };
+            let mut clause = vec![];
+            loop {
+                let v = tokens.next().ok_or("Missing 0 at the end of the last clause")?;
+                let v: isize = v.parse().map_err(|_| format!("Illegal variable '{}'", v))?;
+                if v == 0 {
+                    break;
+                }
+                clause.push(v);
+            }
+            match weight {
+                None => hard.push(clause),
+                Some(weight) => soft.push((weight, clause)),
+            }
+        }
+
+        let max_var = hard.iter().chain(soft.iter().map(|(_, c)| c)).flatten()
+            .map(|v| v.unsigned_abs())
+            .max()
+            .unwrap_or(0);
+        let num_vars = match header {
+            Some((num_vars, num_clauses)) => {
+                if max_var > num_vars {
+                    return Err(format!("Variable {} is out of range", max_var));
+                }
+                match (hard.len() + soft.len()).cmp(&num_clauses) {
+                    core::cmp::Ordering::Less => return Err("Not enough clauses".to_owned()),
+                    core::cmp::Ordering::Greater => return Err("Too many clauses".to_owned()),
+                    core::cmp::Ordering::Equal => num_vars,
+                }
+            }
+            None => max_var,
+        };
+
+        let mut formula = WeightedFormula::new(Formula::new());
+        for _ in 0..num_vars {
+            formula.hard.new_var();
+        }
+        for clause in hard {
+            formula.hard.add_clause(clause.into_iter().map(Literal::from_var));
+        }
+        for (weight, clause) in soft {
+            formula.add_soft_clause(weight, clause.into_iter().map(Literal::from_var));
+        }
+        Ok(formula)
+    }
+
+    /// The total weight of the soft clauses falsified by the assignment, where
+    /// unassigned literals count as false
+    pub fn cost(&self, assignment: &Assignment) -> u64 {
+        self.soft.iter()
+            .filter(|(_, clause)| !clause.iter().any(|&l| assignment.assigned(l)))
+            .map(|(weight, _)| weight)
+            .sum()
+    }
+
+    /// Finds an assignment satisfying the hard clauses which minimizes the weight of the
+    /// falsified soft clauses, by the core-guided Fu-Malik algorithm for weighted
+    /// formulas (WPM1). While the hard and soft clauses together are unsatisfiable, the
+    /// soft clauses of a core each get a fresh relaxation variable, of which at most one
+    /// may be true. Clauses weighing more than the lightest clause of the core are
+    /// split, so that only the weight of the lightest one is relaxed.
+    /// Cores are read off the resolution refutations of `solve_traced`, which share the
+    /// limits of the hard clauses. No assignment is found before the optimum, so there
+    /// is none to return when they run out
+    pub fn solve_maxsat(&self, heuristic: &mut dyn DecisionHeuristic) -> MaxSatResult {
+        // The hard clauses along with the at-most-one constraints on the relaxation
+        // variables, and the soft clauses with their relaxation variables so far
+        let mut relaxed = self.hard.clone();
+        let mut soft = self.soft.clone();
+        let mut budget = self.hard.budget.clone();
+        loop {
+            // The soft clauses come last, so a core consists of the leaves of the
+            // refutation from there on
+            let mut working = relaxed.clone();
+            let first_soft = working.clauses.len();
+            for (_, clause) in &soft {
+                working.add_clause(clause.iter().copied());
+            }
+            let core: Vec<_> = match working.solve_traced_sharing(&mut budget, heuristic) {
+                TraceResult::Sat(model) => {
+                    let mut values: Vec<_> = model.completed_values().into_iter()
+                        .map(Some)
+                        .collect();
+                    values.truncate(self.hard.num_vars());
+                    let model = Assignment::from(values);
+                    let cost = self.cost(&model);
+                    return MaxSatResult::Optimum(model, cost);
+                }
+                TraceResult::Unsat(trace) => trace.leaves().into_iter()
+                    .filter_map(|i| i.checked_sub(first_soft))
+                    .collect(),
+                TraceResult::Unknown => return MaxSatResult::Unknown,
+            };
+            // Without soft clauses in the core, the hard clauses are unsatisfiable
+            let lightest = match core.iter().map(|&i| soft[i].0).min() {
+                Some(lightest) => lightest,
+                None => return MaxSatResult::Unsat,
+            };
+            let mut relaxations = vec![];
+            for i in core {
+                let relaxation = relaxed.new_var().positive();
+                relaxations.push(relaxation);
+                let (weight, clause) = &mut soft[i];
+                if *weight > lightest {
+                    *weight -= lightest;
+                    let copy = clause.iter().copied().chain([relaxation]).collect();
+                    soft.push((lightest, copy));
+                } else {
+                    clause.push(relaxation);
+                }
+            }
+            relaxed.add_at_most_k(&relaxations, 1, CardinalityEncoding::SequentialCounter);
+        }
+    }
+
+    /// Like `solve_maxsat`, but by linear search from above. Each soft clause gets a
+    /// relaxation variable which is true exactly if the clause is falsified, and after
+    /// each model, a sequential weight counter over the relaxation variables demands a
+    /// lower cost, until no model is left. The counter grows with the cost, so this
+    /// suits formulas with light weights, where a cheap model is found early. The
+    /// searches share the limits of the hard clauses, and when those run out, the
+    /// cheapest model so far is returned
+    pub fn solve_maxsat_linear(&self, heuristic: &mut dyn DecisionHeuristic) -> MaxSatResult {
+        let mut relaxed = self.hard.clone();
+        let relaxations: Vec<_> = self.soft.iter()
+            .map(|(weight, clause)| {
+                let relaxation = relaxed.new_var().positive();
+                relaxed.add_clause(clause.iter().copied().chain([relaxation]));
+                for &lit in clause {
+                    relaxed.add_clause([!relaxation, !lit]);
+                }
+                (*weight, relaxation)
+            })
+            .collect();
+        let mut budget = self.hard.budget.clone();
+        let mut best: Option<(Assignment, u64)> = None;
+        loop {
+            // Trying variables false first keeps relaxation variables and counters
+            // false unless they are needed
+            let mut bounded = relaxed.clone();
+            bounded.set_phase(Phase::Negative);
+            if let Some((_, cost)) = &best {
+                if *cost == 0 {
+                    break;
+                }
+                at_most_weight(&mut bounded, &relaxations, cost - 1);
+            }
+            match bounded.solve_sharing(&mut budget, heuristic).0 {
+                SolveResult::Sat(model) => {
+                    let mut values: Vec<_> = model.completed_values().into_iter()
+                        .map(Some)
+                        .collect();
+                    values.truncate(self.hard.num_vars());
+                    let model = Assignment::from(values);
+                    let cost = self.cost(&model);
+                    best = Some((model, cost));
+                }
+                SolveResult::Unsat => break,
+                SolveResult::Unknown => {
+                    return best.map_or(MaxSatResult::Unknown, |(m, c)| MaxSatResult::Best(m, c));
+                }
+            }
+        }
+        best.map_or(MaxSatResult::Unsat, |(model, cost)| MaxSatResult::Optimum(model, cost))
+    }
+
+    /// Solves the formula with the given algorithm
+    pub fn solve_maxsat_with(&self, algorithm: MaxSatAlgorithm,
+        heuristic: &mut dyn DecisionHeuristic) -> MaxSatResult {
+        match algorithm {
+            MaxSatAlgorithm::CoreGuided => self.solve_maxsat(heuristic),
+            MaxSatAlgorithm::Linear => self.solve_maxsat_linear(heuristic),
+        }
+    }
+}
+
+impl Formula {
+    /// Finds a set of the weighted assumptions of least total weight which, once
+    /// dropped, leaves the formula satisfiable under the others, as when the weights
+    /// are the confidence in each assumption. The assumptions become soft unit clauses
+    /// solved with the algorithm, so the ones to drop are those which the assignment of
+    /// the result falsifies, and their weight is its cost. The searches share the
+    /// limits of the formula, and Unsat means that the formula is unsatisfiable by
+    /// itself, so that dropping assumptions doesn't help
+    pub fn assumptions_to_drop(&self, assumptions: &[(u64, Literal)], algorithm: MaxSatAlgorithm,
+        heuristic: &mut dyn DecisionHeuristic) -> MaxSatResult {
+        let mut weighted = WeightedFormula::new(self.clone());
+        for &(weight, lit) in assumptions {
+            weighted.add_soft_clause(weight, [lit]);
+        }
+        weighted.solve_maxsat_with(algorithm, heuristic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use crate::heuristic::Heuristic;
+    use crate::rng::Rng;
+    use crate::solver::{testing, Literal};
+    use super::{MaxSatAlgorithm, MaxSatResult, WeightedFormula};
+
+    // Random hard clauses along with random soft clauses of weights 1 to 4
+    fn random_weighted(rng: &mut Rng) -> WeightedFormula {
+        let mut formula = WeightedFormula::new(testing::random_formula(rng, 6, 4, 3));
+        let soft = testing::random_formula(rng, 6, 7, 2);
+        for clause in soft.clauses() {
+            formula.add_soft_clause(1 + rng.below(4) as u64, clause.iter().copied());
+        }
+        formula
+    }
+
+    // The lowest cost of an assignment satisfying the hard clauses, by trying them all
+    fn optimum(formula: &WeightedFormula) -> Option<u64> {
+        let num_vars = formula.hard.num_vars();
+        (0..1 << num_vars)
+            .map(|bits| testing::values(num_vars, bits))
+            .filter(|values| testing::satisfied_by(&formula.hard, values))
+            .map(|values| formula.cost(&values.into_iter().map(Some).collect::<Vec<_>>().into()))
+            .min()
+    }
+
+    // Checks the algorithm's solutions against the optimum
+    fn check(algorithm: MaxSatAlgorithm) {
+        let solve = |formula: &WeightedFormula| {
+            formula.solve_maxsat_with(algorithm, Heuristic::Ordered.build().as_mut())
+        };
+        let input = "p wcnf 3 5 10\n10 1 2 0\n10 -1 -2 0\n3 1 0\n5 2 0\n1 -3 0\n";
+        let formula = WeightedFormula::parse_wcnf_str(input).expect("Valid WCNF");
+        match solve(&formula) {
+            MaxSatResult::Optimum(model, cost) => assert_eq!((cost, formula.cost(&model)), (3, 3)),
+            _ => panic!("Satisfiable"),
+        }
+        let unsat = WeightedFormula::parse_wcnf_str("p wcnf 1 3 5\n5 1 0\n5 -1 0\n1 1 0\n")
+            .expect("Valid WCNF");
+        assert!(matches!(solve(&unsat), MaxSatResult::Unsat));
+
+        let mut rng = Rng::new(17);
+        for _ in 0..200 {
+            let formula = random_weighted(&mut rng);
+            match solve(&formula) {
+                MaxSatResult::Optimum(model, cost) => {
+                    assert!(testing::is_model(&formula.hard, &model));
+                    assert_eq!(formula.cost(&model), cost);
+                    assert_eq!(Some(cost), optimum(&formula));
+                }
+                MaxSatResult::Unsat => assert_eq!(optimum(&formula), None),
+                _ => panic!("No limits were set"),
+            }
+        }
+    }
+
+    #[test]
+    fn core_guided_finds_the_optimum() {
+        check(MaxSatAlgorithm::CoreGuided);
+    }
+
+    #[test]
+    fn linear_search_finds_the_optimum() {
+        check(MaxSatAlgorithm::Linear);
+    }
+
+    #[test]
+    fn drops_the_lightest_assumptions() {
+        let mut rng = Rng::new(23);
+        for algorithm in [MaxSatAlgorithm::CoreGuided, MaxSatAlgorithm::Linear] {
+            for _ in 0..100 {
+                let formula = testing::random_formula(&mut rng, 6, 5, 3);
+                let assumptions: Vec<_> = (0..1 + rng.below(5))
+                    .map(|_| (rng.below(5) as u64, Literal::new(rng.below(6), rng.chance(0.5))))
+                    .collect();
+                // The lightest assumptions to drop are those a model falsifies, for the
+                // model which falsifies the least weight
+                let lightest = (0..1 << 6)
+                    .map(|bits| testing::values(6, bits))
+                    .filter(|values| testing::satisfied_by(&formula, values))
+                    .map(|values| assumptions.iter()
+                        .filter(|&&(_, lit)| values[lit.id()] == lit.is_negated())
+                        .map(|&(weight, _)| weight)
+                        .sum::<u64>())
+                    .min();
+                let mut heuristic = Heuristic::Ordered.build();
+                match formula.assumptions_to_drop(&assumptions, algorithm, heuristic.as_mut()) {
+                    MaxSatResult::Optimum(model, cost) => {
+                        assert!(testing::is_model(&formula, &model));
+                        let dropped = assumptions.iter()
+                            .filter(|&&(_, lit)| !model.assigned(lit))
+                            .map(|&(weight, _)| weight)
+                            .sum::<u64>();
+                        assert_eq!((Some(cost), Some(dropped)), (lightest, lightest));
+                    }
+                    MaxSatResult::Unsat => assert_eq!(lightest, None),
+                    _ => panic!("No limits were set"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn linear_search_only_claims_the_optimum_when_done() {
+        let mut rng = Rng::new(18);
+        let mut best = 0;
+        for _ in 0..200 {
+            let mut formula = random_weighted(&mut rng);
+            formula.hard().set_conflict_limit(Some(1));
+            match formula.solve_maxsat_linear(Heuristic::Ordered.build().as_mut()) {
+                MaxSatResult::Optimum(model, cost) => {
+                    assert!(testing::is_model(&formula.hard, &model));
+                    assert_eq!(Some(cost), optimum(&formula));
+                }
+                MaxSatResult::Best(model, cost) => {
+                    best += 1;
+                    assert!(testing::is_model(&formula.hard, &model));
+                    assert_eq!(formula.cost(&model), cost);
+                    assert!(Some(cost) >= optimum(&formula));
+                }
+                MaxSatResult::Unsat => assert_eq!(optimum(&formula), None),
+                MaxSatResult::Unknown => (),
+            }
+        }
+        assert!(best > 20);
+    }
+}
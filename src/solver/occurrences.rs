@@ -0,0 +1,133 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use super::{Assignment, Formula, Literal};
+
+// The clauses containing each literal, with which the search keeps counters of the true
+// and the non-false literals of each clause as it assigns literals. An assignment only
+// changes the counters of the clauses of its literal and of its negation, so checking
+// for conflicts and for a model takes constant time per assignment, instead of
+// evaluating the clauses again
+#[derive(Clone, Default)]
+pub(super) struct Occurrences {
+    // The positions of the clauses containing each literal, indexed by Literal::index
+    lists: Vec<Vec<usize>>,
+    // The number of true literals of each clause
+    num_true: Vec<u32>,
+    // The number of literals of each clause which aren't false
+    num_open: Vec<u32>,
+    // The number of clauses with a true literal
+    num_satisfied: usize,
+    // The positions of the clauses all of whose literals are false, in the order they
+    // became falsified
+    falsified: Vec<usize>,
+}
+
+impl Occurrences {
+    // Adds the clause at the position
+    fn add(&mut self, i: usize, clause: &[Literal], assignment: &Assignment) {
+        let (mut num_true, mut num_open) = (0, 0);
+        for &lit in clause {
+            // A literal occurring twice only counts once
+            let list = &mut self.lists[lit.index()];
+            if list.last() != Some(&i) {
+                list.push(i);
+                num_true += assignment.assigned(lit) as u32;
+                num_open += !assignment.assigned(!lit) as u32;
+            }
+        }
+        self.num_true.push(num_true);
+        self.num_open.push(num_open);
+        if num_true > 0 {
+            self.num_satisfied += 1;
+        } else if num_open == 0 {
+            self.falsified.push(i);
+        }
+    }
+}
+
+impl Formula {
+    // Builds the occurrence lists and the counters for the clauses and the current
+    // assignment, which the search keeps up to date from then on
+    pub(super) fn track_occurrences(&mut self) {
+        let mut occurrences = Occurrences {
+            lists: vec![vec![]; 2 * self.num_vars()],
+            ..Occurrences::default()
+        };
+        for (i, clause) in self.clauses.iter().enumerate() {
+            occurrences.add(i, clause, &self.assignment);
+        }
+        self.occurrences = Some(occurrences);
+    }
+
+    // Adds the last clause to the occurrence lists, if they are tracked
+    pub(super) fn track_last_clause(&mut self) {
+        if let Some(occurrences) = &mut self.occurrences {
+            let i = self.clauses.len() - 1;
+            occurrences.add(i, &self.clauses[i], &self.assignment);
+        }
+    }
+
+    // Assigns the literal, counting it in the clauses of the literal and its negation,
+    // each of which is a propagation
+    pub(super) fn set_literal(&mut self, lit: Literal) {
+        self.assignment.assign(lit);
+        let occurrences = self.occurrences.as_mut().expect("Occurrences are tracked");
+        let visited = occurrences.lists[lit.index()].len()
+            + occurrences.lists[(!lit).index()].len();
+        for &i in &occurrences.lists[lit.index()] {
+            occurrences.num_true[i] += 1;
+            if occurrences.num_true[i] == 1 {
+                occurrences.num_satisfied += 1;
+            }
+        }
+        for &i in &occurrences.lists[(!lit).index()] {
+            occurrences.num_open[i] -= 1;
+            if occurrences.num_open[i] == 0 && occurrences.num_true[i] == 0 {
+                occurrences.falsified.push(i);
+            }
+        }
+        self.count_propagations(visited as u64);
+    }
+
+    // Unassigns the literal, taking it back out of the counters
+    pub(super) fn unset_literal(&mut self, lit: Literal) {
+        self.assignment.un_assign(lit);
+        let occurrences = self.occurrences.as_mut().expect("Occurrences are tracked");
+        for &i in &occurrences.lists[lit.index()] {
+            occurrences.num_true[i] -= 1;
+            if occurrences.num_true[i] == 0 {
+                occurrences.num_satisfied -= 1;
+                // Only happens if the literals aren't unassigned in reverse order
+                if occurrences.num_open[i] == 0 {
+                    occurrences.falsified.push(i);
+                }
+            }
+        }
+        let falsified = &mut occurrences.falsified;
+        for &i in &occurrences.lists[(!lit).index()] {
+            occurrences.num_open[i] += 1;
+            if occurrences.num_open[i] == 1 && occurrences.num_true[i] == 0 {
+                if let Some(k) = falsified.iter().position(|&j| j == i) {
+                    falsified.remove(k);
+                }
+            }
+        }
+    }
+
+    // Whether every clause is satisfied by the current assignment
+    pub(super) fn solved(&self) -> bool {
+        let occurrences = self.occurrences.as_ref().expect("Occurrences are tracked");
+        occurrences.num_satisfied == self.clauses.len()
+    }
+
+    // The position of a clause falsified by the current assignment, if any
+    pub(super) fn falsified(&self) -> Option<usize> {
+        let occurrences = self.occurrences.as_ref().expect("Occurrences are tracked");
+        occurrences.falsified.first().copied()
+    }
+
+    // Returns a clause falsified by the current assignment, if any
+    pub(super) fn conflict(&self) -> Option<&[Literal]> {
+        self.falsified().map(|i| &self.clauses[i])
+    }
+}
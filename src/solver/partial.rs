@@ -0,0 +1,69 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use super::{Assignment, Clause, Formula, Literal};
+
+impl Formula {
+    /// Shrinks a model of the formula to a partial one by unassigning variables whose
+    /// values don't matter: every clause the model satisfies keeps a true literal, while
+    /// the unassigned variables are don't-cares, which can take either value. Variables
+    /// are unassigned greedily in order, so the result is minimal in that no single
+    /// variable can be left out, though a smaller partial model may exist. The result
+    /// covers the same variables as the model
+    pub fn shrink_model(&self, model: &Assignment) -> Assignment {
+        let mut shrunk = self.shrink(model);
+        shrunk.0.truncate(model.len());
+        shrunk
+    }
+
+    /// Minimizes a model of the formula to a prime implicant, i.e. a set of literals
+    /// which satisfies every clause but no longer does so with any one of them dropped.
+    /// The literals are those `shrink_model` leaves assigned, in order of variable.
+    /// Returns None if the model doesn't satisfy the formula
+    pub fn prime_implicant(&self, model: &Assignment) -> Option<Vec<Literal>> {
+        let shrunk = self.shrink(model);
+        if !self.clauses.iter().all(|c| c.solved(&shrunk) || is_tautology(c)) {
+            return None;
+        }
+        Some(shrunk.0.iter().enumerate()
+            .filter_map(|(id, negated)| negated.map(|n| Literal::new(id, n)))
+            .collect())
+    }
+
+    // Like shrink_model, but covering every variable of the formula
+    fn shrink(&self, model: &Assignment) -> Assignment {
+        let mut values = model.0.clone();
+        values.resize(self.num_vars(), None);
+        let mut shrunk = Assignment(values);
+
+        // The clauses each variable satisfies, and the number of true literals of each.
+        // Tautologies are satisfied whatever the values, so they don't count
+        let mut satisfied = vec![vec![]; shrunk.len()];
+        let mut true_counts = vec![0usize; self.clauses.len()];
+        for (i, clause) in self.clauses.iter().enumerate() {
+            if is_tautology(clause) {
+                continue;
+            }
+            for &lit in clause {
+                // A literal occurring twice only counts once
+                if shrunk.assigned(lit) && satisfied[lit.id()].last() != Some(&i) {
+                    satisfied[lit.id()].push(i);
+                    true_counts[i] += 1;
+                }
+            }
+        }
+        for (id, clauses) in satisfied.iter().enumerate() {
+            if clauses.iter().all(|&i| true_counts[i] > 1) {
+                for &i in clauses {
+                    true_counts[i] -= 1;
+                }
+                shrunk.0[id] = None;
+            }
+        }
+        shrunk
+    }
+}
+
+// Whether the clause contains some literal along with its negation
+fn is_tautology(clause: &[Literal]) -> bool {
+    clause.iter().any(|&lit| clause.contains(&!lit))
+}
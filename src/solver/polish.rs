@@ -0,0 +1,122 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use super::{Assignment, Clause, Formula, Literal};
+
+impl Formula {
+    /// Improves a model of the formula by hill climbing: flips the variable satisfying
+    /// the most additional soft clauses, as long as that keeps every clause of the
+    /// formula satisfied, for up to `max_flips` flips. Returns the polished model
+    /// along with the number of soft clauses it satisfies.
+    /// The soft clauses may only use variables of the formula
+    pub fn polish(&self, model: &Assignment, soft: &Formula, max_flips: u64) -> (Assignment, usize) {
+        let mut values = model.completed_values();
+        values.resize(self.num_vars(), false);
+
+        // The counts assume each literal occurs once per clause, so duplicates are
+        // removed. Tautologies are left out of the occurrence lists, since no flip
+        // falsifies them
+        let clauses = |formula: &Formula| -> Vec<_> {
+            formula.clauses.iter().map(|c| c.normalized()).collect()
+        };
+        let (hard, soft) = (clauses(self), clauses(soft));
+        let occurrences = |clauses: &[Vec<Literal>]| {
+            let mut occurrences = vec![vec![]; 2 * self.num_vars()];
+            for (i, clause) in clauses.iter().enumerate() {
+                if clause.windows(2).any(|pair| pair[0] == !pair[1]) {
+                    continue;
+                }
+                for lit in clause {
+                    occurrences[lit.index()].push(i);
+                }
+            }
+            occurrences
+        };
+        let true_counts = |clauses: &[Vec<Literal>], values: &[bool]| -> Vec<usize> {
+            clauses.iter()
+                .map(|c| c.iter().filter(|l| values[l.id()] != l.is_negated()).count())
+                .collect()
+        };
+        let (hard_occurrences, soft_occurrences) = (occurrences(&hard), occurrences(&soft));
+        let mut hard_count = true_counts(&hard, &values);
+        let mut soft_count = true_counts(&soft, &values);
+
+        for _ in 0..max_flips {
+            // Flipping a variable falsifies its true literal and makes its negation true
+            let mut best = None;
+            for (id, &value) in values.iter().enumerate() {
+                let lit = Literal::new(id, !value);
+                if hard_occurrences[lit.index()].iter().any(|&i| hard_count[i] == 1) {
+                    continue;
+                }
+                let gained = soft_occurrences[(!lit).index()].iter()
+                    .filter(|&&i| soft_count[i] == 0).count();
+                let lost = soft_occurrences[lit.index()].iter()
+                    .filter(|&&i| soft_count[i] == 1).count();
+                if gained > lost && best.is_none_or(|(_, gain)| gained - lost > gain) {
+                    best = Some((lit, gained - lost));
+                }
+            }
+
+            let lit = match best {
+                Some((lit, _)) => lit,
+                None => break,
+            };
+            values[lit.id()] = !values[lit.id()];
+            for (occurrences, count) in [(&hard_occurrences, &mut hard_count),
+                                         (&soft_occurrences, &mut soft_count)] {
+                occurrences[lit.index()].iter().for_each(|&i| count[i] -= 1);
+                occurrences[(!lit).index()].iter().for_each(|&i| count[i] += 1);
+            }
+        }
+
+        let mut polished = Assignment::new(model.0.len());
+        for (id, &value) in values.iter().enumerate().take(model.0.len()) {
+            polished.assign(Literal::new(id, !value));
+        }
+        (polished, soft_count.iter().filter(|&&c| c > 0).count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use crate::heuristic::Heuristic;
+    use crate::rng::Rng;
+    use crate::solver::testing;
+    use crate::solver::{Assignment, SolveResult};
+
+    #[test]
+    fn repeated_literals_keep_hard_clauses() {
+        let hard = testing::formula("p cnf 2 1\n1 1 0\n");
+        let soft = testing::formula("p cnf 2 1\n-1 0\n");
+        let model = Assignment::from(vec![Some(true), Some(true)]);
+        let (polished, satisfied) = hard.polish(&model, &soft, 100);
+        assert!(testing::is_model(&hard, &polished));
+        assert_eq!(satisfied, 0);
+    }
+
+    #[test]
+    fn polishing_never_breaks_hard_clauses() {
+        let mut rng = Rng::new(3);
+        for _ in 0..300 {
+            let hard = testing::random_formula(&mut rng, 6, 8, 3);
+            let soft = testing::random_formula(&mut rng, 6, 8, 3);
+            let model = match hard.clone().solve(Heuristic::Ordered.build().as_mut()) {
+                SolveResult::Sat(model) => model,
+                _ => continue,
+            };
+            let (polished, satisfied) = hard.polish(&model, &soft, 100);
+            assert!(testing::is_model(&hard, &polished));
+            let values = polished.completed_values();
+            let actual = soft.clauses()
+                .filter(|c| c.iter().any(|l| values[l.id()] != l.is_negated()))
+                .count();
+            assert_eq!(satisfied, actual);
+            let before = model.completed_values();
+            let initial = soft.clauses()
+                .filter(|c| c.iter().any(|l| before[l.id()] != l.is_negated()))
+                .count();
+            assert!(satisfied >= initial);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use crate::heuristic::{Heuristic, Phase};
+use super::{interrupted, Engine, Formula, SolveResult, Stats};
+
+// The heuristics and phases the members of a portfolio cycle through
+const HEURISTICS: [Heuristic; 3] = [Heuristic::Ordered, Heuristic::JeroslowWang, Heuristic::Dlis];
+const PHASES: [Phase; 3] = [Phase::Positive, Phase::Negative, Phase::Occurrence];
+// How often the portfolio checks whether it was interrupted while waiting for an answer
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+impl Formula {
+    /// Solves copies of the formula on the given number of threads at once, and returns
+    /// the first answer along with the counters of the search which found it, stopping
+    /// the others. The first copy searches with the heuristic and settings as given,
+    /// while the others are diversified: each cycles on to another heuristic, and to
+    /// another phase once the heuristics are used up, and runs in chaos mode with its
+    /// own seed, so it breaks ties and orders clauses differently. WalkSAT runs with a
+    /// different seed on each thread instead. Unknown is only returned once every search
+    /// has given up. Which search answers first depends on timing, so the model and the
+    /// counters can differ between runs. Panics if `threads` is 0
+    pub fn solve_portfolio(self, heuristic: Heuristic, threads: usize) -> (SolveResult, Stats) {
+        assert!(threads > 0, "A portfolio needs at least one thread");
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            for i in 0..threads {
+                let mut member = self.clone();
+                let heuristic = member.diversify(i, heuristic);
+                member.set_interrupt(Some(Arc::clone(&stop)));
+                let sender = sender.clone();
+                scope.spawn(move || {
+                    // The portfolio stops listening once it has an answer
+                    let _ = sender.send(member.solve_with_stats(heuristic.build().as_mut()));
+                });
+            }
+            drop(sender);
+
+            let mut last = (SolveResult::Unknown, Stats::default());
+            for _ in 0..threads {
+                let answer = loop {
+                    match receiver.recv_timeout(POLL_INTERVAL) {
+                        Ok(answer) => break answer,
+                        Err(RecvTimeoutError::Timeout) => {
+                            if interrupted(&self.budget.interrupt) {
+                                stop.store(true, Ordering::Relaxed);
+                            }
+                        }
+                        // A search panicked, which the scope passes on
+                        Err(RecvTimeoutError::Disconnected) => return last,
+                    }
+                };
+                if !matches!(answer.0, SolveResult::Unknown) {
+                    stop.store(true, Ordering::Relaxed);
+                    return answer;
+                }
+                last = answer;
+            }
+            last
+        })
+    }
+
+    // Changes the settings of the copy solved by the member of a portfolio with the
+    // given index, returning the heuristic it uses. Only the first member reports
+    // progress and logs, so the others don't repeat it
+    fn diversify(&mut self, i: usize, heuristic: Heuristic) -> Heuristic {
+        if i == 0 {
+            return heuristic;
+        }
+        self.progress = None;
+        self.log = None;
+        self.set_chaos_seed(Some(i as u64));
+        if let Engine::WalkSat { max_flips, seed } = self.engine {
+            self.engine = Engine::WalkSat { max_flips, seed: seed.wrapping_add(i as u64) };
+        }
+        let phase = PHASES.iter().position(|&p| p == self.phase).unwrap_or(0);
+        self.phase = PHASES[(phase + i / HEURISTICS.len()) % PHASES.len()];
+        let first = HEURISTICS.iter().position(|&h| h == heuristic).unwrap_or(0);
+        HEURISTICS[(first + i) % HEURISTICS.len()]
+    }
+}
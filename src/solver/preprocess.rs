@@ -0,0 +1,289 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::config::SolverConfig;
+use super::{Assignment, BinaryStats, Clause, ClauseDb, Conflict, Formula, Literal};
+
+/// Counts of the simplifications made by `Formula::preprocess`, each None if its
+/// step didn't run
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PreprocessStats {
+    /// Symmetry-breaking clauses added
+    pub symmetry_breaking: Option<usize>,
+    /// Subsumed clauses, and literals removed by self-subsuming resolution
+    pub subsumption: Option<(usize, usize)>,
+    pub binary: Option<BinaryStats>,
+    /// Auxiliary variables introduced by splitting long clauses
+    pub aux_vars: Option<usize>,
+    /// Bytes of clause storage returned by garbage collection after the steps removed
+    /// clauses and literals, see `Formula::collect_garbage`
+    pub reclaimed_bytes: usize,
+}
+
+impl Formula {
+    /// Runs the preprocessing steps enabled in the configuration, in the order
+    /// symmetry breaking, subsumption, binary simplification and clause splitting,
+    /// and then collects the garbage they left behind. Subsumption and binary
+    /// simplification are skipped on formulas with fewer than `fast_path_vars` variables,
+    /// while the steps which are off by default run whenever they are enabled
+    pub fn preprocess(&mut self, config: &SolverConfig) -> PreprocessStats {
+        let reclaimed = self.clauses.reclaimed_bytes();
+        let mut stats = PreprocessStats::default();
+        if config.break_symmetries {
+            stats.symmetry_breaking = Some(self.break_symmetries());
+        }
+        let large = self.num_vars() >= config.fast_path_vars;
+        if config.subsumption && large {
+            stats.subsumption = Some(self.subsume_and_strengthen());
+        }
+        if config.binary && large {
+            stats.binary = Some(self.simplify_binary());
+        }
+        if let Some(threshold) = config.ternary_threshold {
+            stats.aux_vars = Some(self.split_long_clauses(threshold));
+        }
+        self.collect_garbage();
+        stats.reclaimed_bytes = self.clauses.reclaimed_bytes() - reclaimed;
+        stats
+    }
+
+    /// Compacts the storage of the clauses, which removing clauses or literals leaves
+    /// gaps in, and returns the number of bytes returned. This also happens on its own
+    /// once the gaps take up more room than the clauses
+    pub fn collect_garbage(&mut self) -> usize {
+        let reclaimed = self.clauses.reclaimed_bytes();
+        self.clauses.compact();
+        self.clauses.reclaimed_bytes() - reclaimed
+    }
+
+    /// Returns the formula with each literal of the cube added as a unit clause
+    pub fn restricted(&self, cube: &[Literal]) -> Formula {
+        let mut restricted = self.clone();
+        restricted.clauses.extend(cube.iter().map(|&l| [l]));
+        restricted
+    }
+
+    /// Returns the formula with the literal asserted and the formula with its negation
+    /// asserted, each simplified by unit propagation
+    pub fn split_on(&self, lit: Literal) -> (Formula, Formula) {
+        let branch = |lit| {
+            let mut branch = self.restricted(&[lit]);
+            branch.propagate_units();
+            branch
+        };
+        (branch(lit), branch(!lit))
+    }
+
+    /// Runs unit propagation to a fixpoint, removing satisfied clauses and false
+    /// literals. The propagated literals are kept as unit clauses, so the result is
+    /// equivalent. If a clause gets falsified, only an empty clause is left instead
+    pub fn propagate_units(&mut self) {
+        let mut fixed = Assignment::new(self.num_vars());
+        if !self.propagate(&mut fixed) {
+            self.clauses = ClauseDb::unsatisfiable();
+            return;
+        }
+
+        let mut reduced = ClauseDb::new();
+        for (id, negated) in fixed.0.iter().enumerate() {
+            reduced.extend(negated.map(|n| [Literal::new(id, n)]));
+        }
+        for clause in self.clauses.iter().filter(|c| !c.solved(&fixed)) {
+            reduced.push(clause.iter().copied().filter(|l| fixed.0[l.id()].is_none()));
+        }
+        self.clauses = reduced;
+    }
+
+    /// Returns the literals implied by unit propagation under the assumptions, ordered
+    /// by variable and excluding the assumptions themselves. No branching is done
+    pub fn propagate_under(&self, assumptions: &[Literal]) -> Result<Vec<Literal>, Conflict> {
+        let mut fixed = Assignment::new(self.num_vars());
+        for &lit in assumptions {
+            if fixed.assigned(!lit) {
+                return Err(Conflict);
+            }
+            fixed.assign(lit);
+        }
+        if !self.propagate(&mut fixed) {
+            return Err(Conflict);
+        }
+
+        Ok(fixed.0.iter().enumerate()
+            .filter_map(|(id, negated)| negated.map(|n| Literal::new(id, n)))
+            .filter(|l| !assumptions.contains(l))
+            .collect())
+    }
+
+    // Extends the assignment with the literals implied by unit propagation.
+    // Returns false if a clause gets falsified
+    pub(super) fn propagate(&self, fixed: &mut Assignment) -> bool {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for clause in self.clauses.iter() {
+                if clause.solved(fixed) {
+                    continue;
+                }
+                let open: Vec<_> = clause.iter().copied()
+                    .filter(|l| fixed.0[l.id()].is_none()).take(2).collect();
+                match open[..] {
+                    [] => return false,
+                    [lit] => {
+                        fixed.assign(lit);
+                        changed = true;
+                    }
+                    _ => (),
+                }
+            }
+        }
+        true
+    }
+
+    /// Removes every clause subsumed by another clause, i.e. every clause which is a
+    /// superset of some other clause, and returns the number of removed clauses
+    pub fn subsume(&mut self) -> usize {
+        for i in 0..self.clauses.len() {
+            self.clauses.normalize(i);
+        }
+
+        let signatures: Vec<_> = self.clauses.iter().map(signature).collect();
+        let mut occurrences = vec![vec![]; 2 * self.num_vars()];
+        for (i, clause) in self.clauses.iter().enumerate() {
+            for lit in clause {
+                occurrences[lit.index()].push(i);
+            }
+        }
+
+        // Shorter clauses are tried as subsumers first, so a clause is always removed
+        // before it would get the chance to subsume anything itself
+        let mut order: Vec<_> = (0..self.clauses.len()).collect();
+        order.sort_by_key(|&i| self.clauses[i].len());
+
+        let mut removed = vec![false; self.clauses.len()];
+        for i in order {
+            if removed[i] {
+                continue;
+            }
+            let clause = &self.clauses[i];
+            // Every clause subsumed by this one contains all of its literals,
+            // so it's enough to look through the shortest occurrence list
+            let lit = match clause.iter().min_by_key(|l| occurrences[l.index()].len()) {
+                Some(lit) => *lit,
+                None => continue,
+            };
+            for &j in &occurrences[lit.index()] {
+                let other = &self.clauses[j];
+                if j != i && !removed[j] && other.len() >= clause.len()
+                    && signatures[i] & !signatures[j] == 0 && is_subset(clause, other) {
+                    removed[j] = true;
+                }
+            }
+        }
+
+        let mut index = 0;
+        self.clauses.retain(|_| {
+            index += 1;
+            !removed[index - 1]
+        });
+        removed.iter().filter(|&&r| r).count()
+    }
+
+    /// Alternates subsumption with self-subsuming resolution until neither applies:
+    /// whenever (a, C) and (!a, D) are clauses with C a subset of D, their resolvent D
+    /// subsumes (!a, D), which can therefore be strengthened by removing !a.
+    /// Returns the number of removed clauses and the number of removed literals
+    pub fn subsume_and_strengthen(&mut self) -> (usize, usize) {
+        let mut subsumed = 0;
+        let mut strengthened = 0;
+        loop {
+            subsumed += self.subsume();
+            match self.strengthen() {
+                0 => return (subsumed, strengthened),
+                removed => strengthened += removed,
+            }
+        }
+    }
+
+    // A single pass of self-subsuming resolution over clauses sorted by `subsume`.
+    // Returns the number of removed literals
+    fn strengthen(&mut self) -> usize {
+        let mut signatures: Vec<_> = self.clauses.iter().map(signature).collect();
+        let mut occurrences = vec![vec![]; 2 * self.num_vars()];
+        for (i, clause) in self.clauses.iter().enumerate() {
+            for lit in clause {
+                occurrences[lit.index()].push(i);
+            }
+        }
+
+        // Literals removed during the pass leave stale occurrences behind,
+        // but the subset checks only look at the current clauses
+        let mut removed = 0;
+        for i in 0..self.clauses.len() {
+            for k in 0..self.clauses[i].len() {
+                let lit = self.clauses[i][k];
+                let mut resolved = self.clauses[i].to_vec();
+                resolved[k] = !lit;
+                let resolved_signature = signature(&resolved);
+
+                for &j in &occurrences[(!lit).index()] {
+                    let other = &self.clauses[j];
+                    if j != i && other.len() >= resolved.len()
+                        && resolved_signature & !signatures[j] == 0 && is_subset(&resolved, other) {
+                        self.clauses.retain_literals(j, |l| l != !lit);
+                        signatures[j] = signature(&self.clauses[j]);
+                        removed += 1;
+                    }
+                }
+            }
+        }
+        removed
+    }
+
+    /// Splits every clause longer than `max_len` into a chain of 3-literal clauses
+    /// linked by fresh auxiliary variables, so (a, b, c, d) becomes (a, b, x) and (!x, c, d).
+    /// The auxiliary variables are left out of the returned model.
+    /// Returns the number of introduced variables. Panics if `max_len` is less than 3
+    pub fn split_long_clauses(&mut self, max_len: usize) -> usize {
+        assert!(max_len >= 3, "Clauses can only be split down to 3 literals");
+
+        let num_vars = self.num_vars();
+        let mut next_id = num_vars;
+        let mut fresh = || {
+            next_id += 1;
+            Literal::new(next_id - 1, false)
+        };
+
+        let mut split = ClauseDb::new();
+        for lits in self.clauses.iter() {
+            let len = lits.len();
+            if len <= max_len {
+                split.push(lits.iter().copied());
+                continue;
+            }
+
+            let mut aux = fresh();
+            split.push([lits[0], lits[1], aux]);
+            for &lit in &lits[2..len - 2] {
+                let next = fresh();
+                split.push([!aux, lit, next]);
+                aux = next;
+            }
+            split.push([!aux, lits[len - 2], lits[len - 1]]);
+        }
+        self.clauses = split;
+        self.assignment.0.resize(next_id, None);
+        next_id - num_vars
+    }
+}
+
+// A bit set over-approximating the literals of the clause, so that most non-subsets
+// can be rejected with a single bitwise operation
+fn signature(clause: &[Literal]) -> u64 {
+    clause.iter().fold(0, |sig, l| sig | 1 << (l.index() % 64))
+}
+
+// Checks whether the first sorted clause is a subset of the second sorted clause
+fn is_subset(sub: &[Literal], sup: &[Literal]) -> bool {
+    let mut sup = sup.iter();
+    sub.iter().all(|l| sup.any(|m| m == l))
+}
@@ -0,0 +1,115 @@
+use alloc::vec::Vec;
+use crate::heuristic::DecisionHeuristic;
+use super::{Assignment, Formula, Literal, SolveResult};
+
+/// Hooks through which an application takes part in the search, in the style of
+/// IPASIR-UP. This allows constraints which are too large to encode up front, such as
+/// acyclicity of a graph, to be encoded lazily by adding clauses as the search needs them.
+/// Every clause the propagator adds must follow from its constraints, since it is kept
+/// for the rest of the search, and the model is only known to satisfy the constraints
+/// once `check_model` accepts it
+pub trait ExternalPropagator {
+    /// Called whenever the search assigns a literal
+    fn notify_assignment(&mut self, _lit: Literal) {}
+
+    /// Called whenever the search unassigns a literal on backtracking
+    fn notify_backtrack(&mut self, _lit: Literal) {}
+
+    /// Called after every assignment until it returns None, to add clauses during search.
+    /// A clause must only be returned once, since it is kept
+    fn external_clause(&mut self, _assignment: &Assignment) -> Option<Vec<Literal>> {
+        None
+    }
+
+    /// Called with every model of the clauses, which assigns all variables. Accept it by
+    /// returning None, or reject it by returning a clause it falsifies
+    fn check_model(&mut self, _model: &Assignment) -> Option<Vec<Literal>> {
+        None
+    }
+}
+
+impl Formula {
+    /// Searches like `solve`, but always with plain DPLL, consulting the propagator
+    /// along the way. Models are only returned once the propagator accepts them
+    pub fn solve_with_propagator(mut self, heuristic: &mut dyn DecisionHeuristic,
+        propagator: &mut dyn ExternalPropagator) -> SolveResult {
+        self.log_start("DPLL and an external propagator");
+        heuristic.init(&self);
+        self.track_occurrences();
+        let result = if self.propagated_dpll(heuristic, propagator) {
+            let mut model = core::mem::replace(&mut self.assignment, Assignment::new(0));
+            model.0.truncate(self.num_input_vars);
+            SolveResult::Sat(model)
+        } else if self.budget.exhausted {
+            SolveResult::Unknown
+        } else {
+            SolveResult::Unsat
+        };
+        self.log_result(&result);
+        result
+    }
+
+    // Like dpll, but adds the clauses of the propagator and completes the assignment
+    // once the clauses are solved, so the propagator can check a total model
+    fn propagated_dpll(&mut self, heuristic: &mut dyn DecisionHeuristic,
+        propagator: &mut dyn ExternalPropagator) -> bool {
+        if self.out_of_budget() {
+            return false;
+        }
+        while let Some(clause) = propagator.external_clause(&self.assignment) {
+            self.log_external(&clause);
+            self.add_clause(clause);
+        }
+        if let Some(clause) = self.conflict() {
+            self.log_conflict(clause);
+            heuristic.on_conflict(clause);
+            self.stats.conflicts += 1;
+            return false;
+        }
+
+        if self.solved() {
+            let rest: Vec<_> = (0..self.num_vars())
+                .filter(|&id| self.assignment.0[id].is_none())
+                .map(|id| self.preferred_literal(id))
+                .collect();
+            for &lit in &rest {
+                self.set_literal(lit);
+                propagator.notify_assignment(lit);
+            }
+            match propagator.check_model(&self.assignment) {
+                None => return true,
+                Some(clause) => {
+                    for &lit in rest.iter().rev() {
+                        self.unset_literal(lit);
+                        propagator.notify_backtrack(lit);
+                    }
+                    // The clause is falsified by the completed model, so the search
+                    // goes on with the clause unsolved
+                    self.log_external(&clause);
+                    self.add_clause(clause);
+                    return self.propagated_dpll(heuristic, propagator);
+                }
+            }
+        }
+
+        let next = self.decision(heuristic.pick_branch_literal(self));
+        self.assign(next, heuristic);
+        propagator.notify_assignment(next);
+        self.count_decision(next);
+        if self.propagated_dpll(heuristic, propagator) {
+            return true;
+        }
+        self.un_assign(next, heuristic);
+        propagator.notify_backtrack(next);
+
+        self.assign(!next, heuristic);
+        propagator.notify_assignment(!next);
+        self.count_decision(!next);
+        if self.propagated_dpll(heuristic, propagator) {
+            return true;
+        }
+        self.un_assign(!next, heuristic);
+        propagator.notify_backtrack(!next);
+        false
+    }
+}
@@ -0,0 +1,427 @@
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::Read;
+use super::{Assignment, Formula, Literal, WeightedFormula};
+
+// A node of a decision diagram over the literals of a constraint, which is either
+// decided already or holds exactly if the literal does
+#[derive(Copy, Clone, PartialEq)]
+enum Node {
+    Const(bool),
+    Lit(Literal),
+}
+
+impl Formula {
+    /// Adds clauses, and auxiliary variables, which hold exactly if the coefficients of
+    /// the true literals sum to at least the bound. The constraint is translated through
+    /// its binary decision diagram, with a variable for each node which is defined both
+    /// ways, so once the literals are assigned the nodes have only one value left
+    pub fn add_pb_at_least(&mut self, terms: &[(i64, Literal)], bound: i64) {
+        let terms = terms.iter().map(|&(coefficient, lit)| (i128::from(coefficient), lit));
+        self.add_pb(terms, i128::from(bound));
+    }
+
+    /// Like `add_pb_at_least`, but for a sum of at most the bound
+    pub fn add_pb_at_most(&mut self, terms: &[(i64, Literal)], bound: i64) {
+        // Negating is exact in i128, even for i64::MIN
+        let terms = terms.iter().map(|&(coefficient, lit)| (-i128::from(coefficient), lit));
+        self.add_pb(terms, -i128::from(bound));
+    }
+
+    /// Like `add_pb_at_least`, but for a sum equal to the bound
+    pub fn add_pb_equal(&mut self, terms: &[(i64, Literal)], bound: i64) {
+        self.add_pb_at_least(terms, bound);
+        self.add_pb_at_most(terms, bound);
+    }
+
+    // Adds the constraint that the sum is at least the bound, see add_pb_at_least
+    fn add_pb(&mut self, terms: impl Iterator<Item = (i128, Literal)>, mut bound: i128) {
+        // Negative coefficients count the negated literal, as a * l = a + -a * !l
+        let mut terms: Vec<_> = terms
+            .filter(|&(coefficient, _)| coefficient != 0)
+            .map(|(coefficient, lit)| {
+                if coefficient < 0 {
+                    bound -= coefficient;
+                    (coefficient.unsigned_abs(), !lit)
+                } else {
+                    (coefficient.unsigned_abs(), lit)
+                }
+            })
+            .collect();
+        // Heavy literals first keep the diagram small
+        terms.sort_unstable_by_key(|&(weight, _)| core::cmp::Reverse(weight));
+        let mut rest: Vec<_> = terms.iter().rev()
+            .scan(0u128, |sum, &(weight, _)| {
+                *sum = sum.saturating_add(weight);
+                Some(*sum)
+            })
+            .collect();
+        rest.reverse();
+        rest.push(0);
+        match self.pb_node(&terms, &rest, 0, bound, &mut BTreeMap::new()) {
+            Node::Const(true) => (),
+            Node::Const(false) => self.add_clause([]),
+            Node::Lit(root) => self.add_clause([root]),
+        }
+    }
+
+    // The node which holds exactly if the terms from the i-th on, which weigh rest[i] in
+    // total, sum to at least the bound
+    fn pb_node(&mut self, terms: &[(u128, Literal)], rest: &[u128], i: usize, bound: i128,
+        memo: &mut BTreeMap<(usize, i128), Node>) -> Node {
+        if bound <= 0 {
+            return Node::Const(true);
+        }
+        if rest[i] < bound.unsigned_abs() {
+            return Node::Const(false);
+        }
+        if let Some(&node) = memo.get(&(i, bound)) {
+            return node;
+        }
+        let (weight, lit) = terms[i];
+        let reduced = bound.saturating_sub_unsigned(weight);
+        let then = self.pb_node(terms, rest, i + 1, reduced, memo);
+        let otherwise = self.pb_node(terms, rest, i + 1, bound, memo);
+        let node = self.if_then_else(lit, then, otherwise);
+        memo.insert((i, bound), node);
+        node
+    }
+
+    // A node which holds exactly if the first node does when the literal is true, and
+    // the second one does otherwise
+    fn if_then_else(&mut self, lit: Literal, then: Node, otherwise: Node) -> Node {
+        match (then, otherwise) {
+            _ if then == otherwise => then,
+            (Node::Const(true), Node::Const(false)) => Node::Lit(lit),
+            (Node::Const(false), Node::Const(true)) => Node::Lit(!lit),
+            _ => {
+                let node = self.new_var().positive();
+                for (condition, branch) in [(lit, then), (!lit, otherwise)] {
+                    match branch {
+                        Node::Const(value) => {
+                            let node = if value { node } else { !node };
+                            self.add_clause([!condition, node]);
+                        }
+                        Node::Lit(branch) => {
+                            self.add_clause([!condition, !branch, node]);
+                            self.add_clause([!condition, branch, !node]);
+                        }
+                    }
+                }
+                Node::Lit(node)
+            }
+        }
+    }
+}
+
+/// Linear pseudo-Boolean constraints, translated to clauses, with an optional linear
+/// objective to minimize, as read from the OPB format
+#[derive(Clone)]
+pub struct PbProblem {
+    formula: Formula,
+    // The number of variables of the problem, which come before the auxiliary ones
+    num_vars: usize,
+    objective: Option<Vec<(i64, Literal)>>,
+    // Whether the objective was given by `max:`, so its terms are negated
+    maximize: bool,
+}
+
+impl PbProblem {
+    /// Parses an OPB file, see `parse_opb_str`
+    #[cfg(feature = "std")]
+    pub fn parse_opb(mut file: File) -> Result<PbProblem, String> {
+        let mut buf = String::new();
+        file.read_to_string(&mut buf).map_err(|_| "Error while reading file")?;
+        PbProblem::parse_opb_str(&buf)
+    }
+
+    /// Parses OPB text, i.e. an optional objective such as `min: +2 x1 -1 ~x2 ;`, where
+    /// `max:` is turned into minimizing the negation, followed by constraints such as
+    /// `+1 x1 +3 x3 >= 2 ;` with one of the relations `>=`, `<=` and `=`. Lines starting
+    /// with `*` are comments, and the first one may declare the numbers of variables and
+    /// constraints as `#variable= n #constraint= m`
+    pub fn parse_opb_str(input: &str) -> Result<PbProblem, String> {
+        let mut header = None;
+        let mut body = String::new();
+        for line in input.lines() {
+            if let Some(comment) = line.strip_prefix('*') {
+                if header.is_none() && body.is_empty() && comment.contains("#variable=") {
+                    header = Some(parse_opb_header(comment)?);
+                }
+            } else {
+                body.push_str(line);
+                body.push('\n');
+            }
+        }
+
+        let mut objective = None;
+        let mut maximize = false;
+        let mut constraints = vec![];
+        let mut statements: Vec<_> = body.split(';').collect();
+        if statements.pop().is_some_and(|last| !last.trim().is_empty()) {
+            return Err("Missing ; at the end of the last constraint".to_owned());
+        }
+        for (i, statement) in statements.into_iter().enumerate() {
+            let mut tokens = statement.split_whitespace().peekable();
+            let sense = tokens.next_if(|&t| t == "min:" || t == "max:");
+            if let Some(sense) = sense {
+                if i > 0 {
+                    return Err("The objective must come first".to_owned());
+                }
+                let mut terms = parse_terms(&mut tokens)?;
+                if let Some(token) = tokens.next() {
+                    return Err(format!("Unexpected '{}' in the objective", token));
+                }
+                maximize = sense == "max:";
+                if maximize {
+                    for (coefficient, _) in &mut terms {
+                        *coefficient = coefficient.checked_neg()
+                            .ok_or("A coefficient of the objective is out of range")?;
+                    }
+                }
+                objective = Some(terms);
+                continue;
+            }
+            let terms = parse_terms(&mut tokens)?;
+            let relation = tokens.next().ok_or("Missing relation in a constraint")?;
+            let bound = tokens.next().ok_or("Missing bound in a constraint")?;
+            let bound: i64 = bound.parse().map_err(|_| format!("Illegal bound '{}'", bound))?;
+            if let Some(token) = tokens.next() {
+                return Err(format!("Unexpected '{}' after a constraint", token));
+            }
+            match relation {
+                ">=" | "<=" | "=" => constraints.push((terms, relation, bound)),
+                _ => return Err(format!("Illegal relation '{}'", relation)),
+            }
+        }
+
+        let max_var = constraints.iter().map(|(terms, _, _)| terms)
+            .chain(&objective)
+            .flatten()
+            .map(|(_, lit)| lit.id() + 1)
+            .max()
+            .unwrap_or(0);
+        let num_vars = match header {
+            Some((num_vars, num_constraints)) => {
+                if max_var > num_vars {
+                    return Err(format!("Variable {} is out of range", max_var));
+                }
+                match constraints.len().cmp(&num_constraints) {
+                    core::cmp::Ordering::Less => {
+                        return Err("Not enough constraints".to_owned());
+                    }
+                    core::cmp::Ordering::Greater => {
+                        return Err("Too many constraints".to_owned());
+                    }
+                    core::cmp::Ordering::Equal => num_vars,
+                }
+            }
+            None => max_var,
+        };
+
+        let mut formula = Formula::new();
+        for _ in 0..num_vars {
+            formula.new_var();
+        }
+        for (terms, relation, bound) in constraints {
+            match relation {
+                ">=" => formula.add_pb_at_least(&terms, bound),
+                "<=" => formula.add_pb_at_most(&terms, bound),
+                _ => formula.add_pb_equal(&terms, bound),
+            }
+        }
+        Ok(PbProblem { formula, num_vars, objective, maximize })
+    }
+
+    /// The clauses the constraints translate to, whose variables beyond `num_vars` are
+    /// auxiliary
+    pub fn formula(&self) -> &Formula {
+        &self.formula
+    }
+
+    /// The number of variables of the problem
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    /// The terms of the objective to minimize, if any, which are negated for a `max:`
+    /// objective
+    pub fn objective(&self) -> Option<&[(i64, Literal)]> {
+        self.objective.as_deref()
+    }
+
+    /// The value of the objective as given, i.e. with its original sign for a `max:`
+    /// objective, under the assignment, where unassigned literals count as false, or 0
+    /// without an objective
+    pub fn objective_value(&self, assignment: &Assignment) -> i64 {
+        let value: i64 = self.objective.iter().flatten()
+            .filter(|&&(_, lit)| assignment.assigned(lit))
+            .map(|(coefficient, _)| coefficient)
+            .sum();
+        if self.maximize { -value } else { value }
+    }
+
+    /// The constraints as hard clauses, with a soft clause for each term of the
+    /// objective, so that a solution of minimum cost minimizes the objective. A term
+    /// `a l` is the soft clause `!l` of weight a if a is positive, and `l` of weight -a
+    /// otherwise, so the cost exceeds the objective by the sum of the negative
+    /// coefficients
+    pub fn to_weighted(&self) -> WeightedFormula {
+        let mut weighted = WeightedFormula::new(self.formula.clone());
+        for &(coefficient, lit) in self.objective.iter().flatten() {
+            let clause = if coefficient > 0 { !lit } else { lit };
+            weighted.add_soft_clause(coefficient.unsigned_abs(), [clause]);
+        }
+        weighted
+    }
+}
+
+// Parses the numbers of variables and constraints from the header comment
+fn parse_opb_header(comment: &str) -> Result<(usize, usize), String> {
+    let tokens: Vec<_> = comment.split_whitespace().collect();
+    let value = |key: &str| {
+        let position = tokens.iter().position(|&t| t == key)
+            .ok_or(format!("Missing {} in the header", key))?;
+        tokens.get(position + 1).and_then(|v| v.parse().ok())
+            .ok_or(format!("Invalid {} in the header", key))
+    };
+    Ok((value("#variable=")?, value("#constraint=")?))
+}
+
+// Parses terms such as `+2 x1 -1 ~x3` until a token which isn't a coefficient
+fn parse_terms<'a>(tokens: &mut core::iter::Peekable<impl Iterator<Item = &'a str>>)
+    -> Result<Vec<(i64, Literal)>, String> {
+    let mut terms = vec![];
+    while let Some(coefficient) = tokens.next_if(|t| t.parse::<i64>().is_ok()) {
+        let coefficient = coefficient.parse().unwrap();
+        let lit = tokens.next().ok_or("Missing literal after a coefficient")?;
+        terms.push((coefficient, parse_opb_literal(lit)?));
+        if tokens.peek().is_some_and(|t| t.starts_with('x') || t.starts_with('~')) {
+            return Err("Products of literals are not supported".to_owned());
+        }
+    }
+    Ok(terms)
+}
+
+// Parses a literal such as `x3` or `~x3`
+fn parse_opb_literal(token: &str) -> Result<Literal, String> {
+    let (negated, var) = match token.strip_prefix('~') {
+        Some(var) => (true, var),
+        None => (false, token),
+    };
+    let id: usize = var.strip_prefix('x')
+        .and_then(|id| id.parse().ok())
+        .filter(|&id| id > 0)
+        .ok_or(format!("Illegal literal '{}'", token))?;
+    Ok(Literal::new(id - 1, negated))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use crate::heuristic::Heuristic;
+    use crate::rng::Rng;
+    use crate::solver::testing;
+    use crate::solver::{Assignment, Formula, Literal, MaxSatResult};
+    use super::PbProblem;
+
+    // The sum of the coefficients of the true literals
+    fn sum(terms: &[(i64, Literal)], values: &[bool]) -> i64 {
+        terms.iter()
+            .filter(|&&(_, lit)| values[lit.id()] != lit.is_negated())
+            .map(|&(coefficient, _)| coefficient)
+            .sum()
+    }
+
+    // An optimal solution of the problem, which must be satisfiable
+    fn optimum(problem: &PbProblem) -> Assignment {
+        match problem.to_weighted().solve_maxsat(Heuristic::Ordered.build().as_mut()) {
+            MaxSatResult::Optimum(model, _) => model,
+            _ => panic!("Satisfiable"),
+        }
+    }
+
+    #[test]
+    fn encodings_have_a_model_for_each_solution() {
+        let mut rng = Rng::new(19);
+        for _ in 0..300 {
+            let num_vars = 5;
+            let terms: Vec<_> = (0..1 + rng.below(6))
+                .map(|_| {
+                    let coefficient = rng.below(11) as i64 - 5;
+                    (coefficient, Literal::new(rng.below(num_vars), rng.chance(0.5)))
+                })
+                .collect();
+            let bound = rng.below(17) as i64 - 6;
+            let mut formula = Formula::new();
+            for _ in 0..num_vars {
+                formula.new_var();
+            }
+            let relation = rng.below(3);
+            match relation {
+                0 => formula.add_pb_at_least(&terms, bound),
+                1 => formula.add_pb_at_most(&terms, bound),
+                _ => formula.add_pb_equal(&terms, bound),
+            }
+            // The auxiliary variables are defined by the others, so each solution
+            // extends to exactly one model
+            let solutions = (0..1 << num_vars)
+                .map(|bits| sum(&terms, &testing::values(num_vars, bits)))
+                .filter(|&sum| match relation {
+                    0 => sum >= bound,
+                    1 => sum <= bound,
+                    _ => sum == bound,
+                })
+                .count();
+            assert_eq!(formula.count_models(), Some(solutions as u128));
+        }
+    }
+
+    #[test]
+    fn encodings_handle_extreme_coefficients() {
+        // Negating i64::MIN must not be off by one
+        let (x, y) = (Literal::new(0, false), Literal::new(1, false));
+        let cases = [
+            (vec![(i64::MIN, x)], i64::MIN, 2u128),
+            (vec![(i64::MIN, x), (-1, y)], i64::MIN, 2),
+            (vec![(i64::MIN, x), (i64::MAX, y)], -1, 2),
+            (vec![(i64::MAX, x), (i64::MAX, y)], i64::MAX, 3),
+        ];
+        for (terms, bound, models) in &cases {
+            let mut formula = Formula::new();
+            formula.new_var();
+            formula.new_var();
+            formula.add_pb_at_most(terms, *bound);
+            assert_eq!(formula.count_models(), Some(*models));
+        }
+        assert!(PbProblem::parse_opb_str("max: -9223372036854775808 x1 ;\n").is_err());
+    }
+
+    #[test]
+    fn solves_small_instances() {
+        let input = "* #variable= 3 #constraint= 2\nmin: +2 x1 +3 x2 -1 ~x3 ;\n\
+            +1 x1 +1 x2 +1 x3 >= 2 ;\n+2 x1 -1 x3 <= 1 ;\n";
+        let problem = PbProblem::parse_opb_str(input).expect("Valid OPB");
+        let model = optimum(&problem);
+        // x1 and x3 are the cheapest pair, and 2 x1 - x3 is then 1
+        assert_eq!(problem.objective_value(&model), 2);
+        assert!(testing::is_model(problem.formula(), &model));
+
+        // The same constraints with the objective negated have the same optimum
+        let input = input.replace("min: +2 x1 +3 x2 -1 ~x3", "max: -2 x1 -3 x2 +1 ~x3");
+        let problem = PbProblem::parse_opb_str(&input).expect("Valid OPB");
+        assert_eq!(problem.objective_value(&optimum(&problem)), -2);
+
+        let unsat = PbProblem::parse_opb_str("+1 x1 +1 x2 >= 2 ;\n+1 ~x1 >= 1 ;\n")
+            .expect("Valid OPB");
+        assert!(!testing::satisfiable(unsat.formula()));
+    }
+}
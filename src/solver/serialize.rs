@@ -0,0 +1,71 @@
+use alloc::format;
+use alloc::vec::Vec;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use super::{Formula, Literal, Var};
+
+// The form formulas are serialized in, with clauses of DIMACS literals
+#[derive(Serialize, Deserialize)]
+struct DimacsFormula {
+    num_vars: usize,
+    clauses: Vec<Vec<Literal>>,
+}
+
+impl Serialize for Literal {
+    /// Serializes the literal as a DIMACS literal, e.g. -42
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        i32::from(*self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Literal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match i32::deserialize(deserializer)? {
+            0 => Err(D::Error::custom("0 is not a DIMACS literal")),
+            var => Ok(Literal::from(var)),
+        }
+    }
+}
+
+impl Serialize for Var {
+    /// Serializes the variable as a DIMACS variable, e.g. 42
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        i32::from(*self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Var {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match i32::deserialize(deserializer)? {
+            var if var > 0 => Ok(Var::from(var)),
+            var => Err(D::Error::custom(format!("{} is not a DIMACS variable", var))),
+        }
+    }
+}
+
+impl Serialize for Formula {
+    /// Serializes the variable count and the clauses, leaving out solver settings
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        DimacsFormula {
+            num_vars: self.num_vars(),
+            clauses: self.clauses().map(<[Literal]>::to_vec).collect(),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Formula {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let DimacsFormula { num_vars, clauses } = DimacsFormula::deserialize(deserializer)?;
+        let mut formula = Formula::new();
+        for _ in 0..num_vars {
+            formula.new_var();
+        }
+        for clause in clauses {
+            if let Some(lit) = clause.iter().find(|l| l.id() >= num_vars) {
+                return Err(D::Error::custom(format!("Literal {} is out of range", lit)));
+            }
+            formula.add_clause(clause);
+        }
+        Ok(formula)
+    }
+}
@@ -0,0 +1,89 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use super::{Clause, Formula, Literal};
+
+// Variable pairs tested for being interchangeable, bounding the cost on large formulas
+const MAX_SYMMETRY_PAIRS: usize = 1 << 16;
+
+impl Formula {
+    /// Looks for pairs of variables x < y which can be swapped without changing the
+    /// set of clauses, and adds the lex-leader clause (!x, y) for each of them. Any model
+    /// with x true and y false stays a model after swapping them, so this keeps the
+    /// formula satisfiable exactly when it was. Frozen variables are left out, since
+    /// clauses added later may tell them apart. Returns the number of added clauses
+    pub fn break_symmetries(&mut self) -> usize {
+        let clauses: Vec<_> = self.clauses.iter().map(Clause::normalized).collect();
+        let mut occurrences = vec![vec![]; self.num_vars()];
+        for (i, clause) in clauses.iter().enumerate() {
+            for lit in clause {
+                if occurrences[lit.id()].last() != Some(&i) {
+                    occurrences[lit.id()].push(i);
+                }
+            }
+        }
+
+        // Only variables with the same occurrence profile can be interchangeable
+        let mut profiles = vec![vec![]; self.num_vars()];
+        for clause in &clauses {
+            for lit in clause {
+                profiles[lit.id()].push((clause.len(), lit.is_negated()));
+            }
+        }
+        let mut groups = BTreeMap::new();
+        for (id, mut profile) in profiles.into_iter().enumerate() {
+            profile.sort_unstable();
+            groups.entry(profile).or_insert_with(Vec::new).push(id);
+        }
+        let mut groups: Vec<_> = groups.into_iter()
+            .filter(|(profile, ids)| !profile.is_empty() && ids.len() > 1)
+            .map(|(_, ids)| ids)
+            .collect();
+        groups.sort_unstable();
+
+        let pairs = groups.iter()
+            .flat_map(|ids| ids.iter().enumerate()
+                .flat_map(move |(i, &x)| ids[i + 1..].iter().map(move |&y| (x, y))))
+            .take(MAX_SYMMETRY_PAIRS);
+        let mut added = 0;
+        let mut breaking = vec![];
+        for (x, y) in pairs {
+            if !self.frozen.contains(&x) && !self.frozen.contains(&y)
+                && is_transposition_symmetry(&clauses, &occurrences, x, y) {
+                breaking.push([Literal::new(x, true), Literal::new(y, false)]);
+                added += 1;
+            }
+        }
+        self.clauses.extend(breaking);
+        added
+    }
+}
+
+// Whether swapping the variables maps the clauses containing either of them onto
+// themselves, which are the only clauses the swap changes
+fn is_transposition_symmetry(clauses: &[Vec<Literal>], occurrences: &[Vec<usize>],
+    x: usize, y: usize) -> bool {
+    let swap = |lit: Literal| match lit.id() {
+        id if id == x => Literal::new(y, lit.is_negated()),
+        id if id == y => Literal::new(x, lit.is_negated()),
+        _ => lit,
+    };
+    let mut affected: Vec<_> = occurrences[x].iter().chain(&occurrences[y]).copied().collect();
+    affected.sort_unstable();
+    affected.dedup();
+
+    let mut original: Vec<_> = affected.iter().map(|&i| &clauses[i][..]).collect();
+    let mut swapped: Vec<_> = affected.iter()
+        .map(|&i| {
+            let mut clause: Vec<_> = clauses[i].iter().map(|&l| swap(l)).collect();
+            clause.sort_by_key(|l| l.index());
+            clause
+        })
+        .collect();
+    let by_indices = |a: &&[Literal], b: &&[Literal]| {
+        a.iter().map(|l| l.index()).cmp(b.iter().map(|l| l.index()))
+    };
+    original.sort_unstable_by(by_indices);
+    swapped.sort_unstable_by(|a, b| by_indices(&&a[..], &&b[..]));
+    original.iter().zip(&swapped).all(|(a, b)| a[..] == b[..])
+}
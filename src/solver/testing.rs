@@ -0,0 +1,59 @@
+// Helpers for the tests of the solver modules, which check the algorithms against brute
+// force on formulas small enough to enumerate
+
+use alloc::vec::Vec;
+use crate::rng::Rng;
+use super::{Assignment, Formula, Literal};
+
+// Parses the DIMACS text, which must be valid
+pub(super) fn formula(input: &str) -> Formula {
+    Formula::parse_dimacs_str(input).expect("Valid DIMACS")
+}
+
+// A random formula over the variables whose clauses have up to `max_len` literals,
+// including repeated literals, tautologies and the occasional empty clause
+pub(super) fn random_formula(rng: &mut Rng, num_vars: usize, num_clauses: usize,
+    max_len: usize) -> Formula {
+    let mut formula = Formula::new();
+    for _ in 0..num_vars {
+        formula.new_var();
+    }
+    for _ in 0..num_clauses {
+        let len = if rng.chance(0.02) { 0 } else { 1 + rng.below(max_len) };
+        let clause: Vec<_> = (0..len)
+            .map(|_| Literal::new(rng.below(num_vars), rng.chance(0.5)))
+            .collect();
+        formula.add_clause(clause);
+    }
+    formula
+}
+
+// The value of each variable in the assignment numbered `bits`
+pub(super) fn values(num_vars: usize, bits: u64) -> Vec<bool> {
+    (0..num_vars).map(|id| bits >> id & 1 == 1).collect()
+}
+
+// Whether every clause has a true literal under the values
+pub(super) fn satisfied_by(formula: &Formula, values: &[bool]) -> bool {
+    formula.clauses().all(|c| c.iter().any(|l| values[l.id()] != l.is_negated()))
+}
+
+// Whether every clause has a true literal in the assignment, in which unassigned
+// variables can take either value
+pub(super) fn is_model(formula: &Formula, model: &Assignment) -> bool {
+    model.len() >= formula.num_vars()
+        && formula.clauses().all(|c| c.iter().any(|&l| model.assigned(l)))
+}
+
+// The number of models, by trying every assignment
+pub(super) fn count_models(formula: &Formula) -> u128 {
+    let num_vars = formula.num_vars();
+    assert!(num_vars < 20, "Too many variables to enumerate");
+    (0..1 << num_vars).filter(|&bits| satisfied_by(formula, &values(num_vars, bits))).count()
+        as u128
+}
+
+// Whether the formula has a model, by trying every assignment
+pub(super) fn satisfiable(formula: &Formula) -> bool {
+    count_models(formula) > 0
+}
@@ -0,0 +1,206 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::heuristic::DecisionHeuristic;
+use super::{Assignment, Budget, Formula, Literal, Stats};
+
+/// A resolution derivation of the empty clause. The clauses of the formula come first,
+/// followed by the resolvents in the order they were derived
+pub struct Trace {
+    // Each clause along with the indices of the two clauses it was resolved from,
+    // which is empty for the clauses of the formula
+    steps: Vec<(Vec<Literal>, Vec<usize>)>,
+}
+
+/// The outcome of `Formula::solve_traced`
+pub enum TraceResult {
+    /// The formula is satisfied by the assignment
+    Sat(Assignment),
+    /// The formula is unsatisfiable, as the derivation shows
+    Unsat(Trace),
+    /// The search gave up without an answer
+    Unknown,
+}
+
+// How the traced search below an assignment ended
+enum Traced {
+    // The formula is solved by the assignment
+    Solved,
+    // A traced clause falsified by the assignment, by its index
+    Refuted(usize),
+    GaveUp,
+}
+
+impl Trace {
+    /// The clauses of the derivation along with the indices of their antecedents
+    pub fn steps(&self) -> impl Iterator<Item = (&[Literal], &[usize])> {
+        self.steps.iter().map(|(clause, antecedents)| (&clause[..], &antecedents[..]))
+    }
+
+    // The indices of the clauses of the formula which the derivation of the empty clause
+    // resolves from, in increasing order
+    pub(super) fn leaves(&self) -> Vec<usize> {
+        let mut used = vec![false; self.steps.len()];
+        let mut stack: Vec<_> = self.steps.iter().position(|(c, _)| c.is_empty()).into_iter()
+            .collect();
+        while let Some(i) = stack.pop() {
+            if !used[i] {
+                used[i] = true;
+                stack.extend(&self.steps[i].1);
+            }
+        }
+        (0..self.steps.len()).filter(|&i| used[i] && self.steps[i].1.is_empty()).collect()
+    }
+}
+
+impl Formula {
+    /// Searches like `solve`, but always with plain DPLL on the clauses as given, and
+    /// records how each conflict follows from the clauses by resolution. Returns a
+    /// model, or the derivation of the empty clause if the formula is unsatisfiable.
+    /// The limits and the interrupt apply as they do to `solve`
+    pub fn solve_traced(self, heuristic: &mut dyn DecisionHeuristic) -> TraceResult {
+        self.solve_traced_with_stats(heuristic).0
+    }
+
+    // Like solve_traced, but as one of several searches sharing the budget, see
+    // solve_sharing
+    pub(super) fn solve_traced_sharing(mut self, budget: &mut Budget,
+        heuristic: &mut dyn DecisionHeuristic) -> TraceResult {
+        budget.start();
+        self.budget = budget.clone();
+        let (result, stats) = self.solve_traced_with_stats(heuristic);
+        budget.spend(&stats);
+        result
+    }
+
+    fn solve_traced_with_stats(mut self, heuristic: &mut dyn DecisionHeuristic)
+        -> (TraceResult, Stats) {
+        let mut trace = Trace {
+            steps: self.clauses.iter().map(|c| (c.to_vec(), vec![])).collect(),
+        };
+        self.budget.start();
+        heuristic.init(&self);
+        self.track_occurrences();
+        let result = match self.traced_dpll(heuristic, &mut trace) {
+            Traced::Solved => {
+                self.assignment.0.truncate(self.num_input_vars);
+                TraceResult::Sat(::core::mem::replace(&mut self.assignment, Assignment::new(0)))
+            }
+            Traced::Refuted(_) => TraceResult::Unsat(trace),
+            Traced::GaveUp => TraceResult::Unknown,
+        };
+        (result, self.stats)
+    }
+
+    // Like dpll, but on conflicts returns the index of a traced clause falsified by the
+    // current assignment. That clause is the resolvent of the clauses from the two
+    // branches on the decision
+    fn traced_dpll(&mut self, heuristic: &mut dyn DecisionHeuristic, trace: &mut Trace)
+        -> Traced {
+        if let Some(i) = self.falsified() {
+            heuristic.on_conflict(&self.clauses[i]);
+            self.stats.conflicts += 1;
+            return Traced::Refuted(i);
+        }
+        if self.solved() {
+            return Traced::Solved;
+        }
+        if self.out_of_budget() {
+            return Traced::GaveUp;
+        }
+
+        let next = self.decision(heuristic.pick_branch_literal(self));
+        self.assign(next, heuristic);
+        self.count_decision(next);
+        let first = match self.traced_dpll(heuristic, trace) {
+            Traced::Refuted(first) => first,
+            ended => return ended,
+        };
+        self.un_assign(next, heuristic);
+        // The clause is falsified without the decision, so the other branch is not needed
+        if !trace.steps[first].0.contains(&!next) {
+            return Traced::Refuted(first);
+        }
+
+        self.assign(!next, heuristic);
+        self.count_decision(!next);
+        let second = match self.traced_dpll(heuristic, trace) {
+            Traced::Refuted(second) => second,
+            ended => return ended,
+        };
+        self.un_assign(!next, heuristic);
+        if !trace.steps[second].0.contains(&next) {
+            return Traced::Refuted(second);
+        }
+
+        let mut resolvent: Vec<_> = trace.steps[first].0.iter()
+            .chain(&trace.steps[second].0)
+            .copied()
+            .filter(|l| l.id() != next.id())
+            .collect();
+        resolvent.sort_by_key(|l| l.index());
+        resolvent.dedup();
+        trace.steps.push((resolvent, vec![first, second]));
+        Traced::Refuted(trace.steps.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::sync::atomic::AtomicBool;
+    use crate::generate;
+    use crate::heuristic::Heuristic;
+    use crate::rng::Rng;
+    use crate::solver::testing;
+    use super::{Trace, TraceResult};
+
+    // Whether each resolvent follows from its antecedents by resolving on some variable,
+    // and the empty clause is derived
+    fn is_refutation(trace: &Trace) -> bool {
+        let steps: Vec<_> = trace.steps().collect();
+        steps.iter().any(|(clause, _)| clause.is_empty())
+            && steps.iter().all(|&(clause, antecedents)| match *antecedents {
+                [] => true,
+                [first, second] => {
+                    let (first, second) = (steps[first].0, steps[second].0);
+                    first.iter().any(|&l| second.contains(&!l) && !clause.contains(&l)
+                        && !clause.contains(&!l))
+                        && clause.iter().all(|l| first.contains(l) || second.contains(l))
+                }
+                _ => false,
+            })
+    }
+
+    #[test]
+    fn agrees_with_enumeration() {
+        let mut rng = Rng::new(21);
+        let mut unsat = 0;
+        for _ in 0..300 {
+            let formula = testing::random_formula(&mut rng, 6, 14, 3);
+            match formula.clone().solve_traced(Heuristic::Ordered.build().as_mut()) {
+                TraceResult::Sat(model) => assert!(testing::is_model(&formula, &model)),
+                TraceResult::Unsat(trace) => {
+                    unsat += 1;
+                    assert!(!testing::satisfiable(&formula));
+                    assert!(is_refutation(&trace));
+                }
+                TraceResult::Unknown => panic!("No limits were set"),
+            }
+        }
+        assert!(unsat > 30 && unsat < 270);
+    }
+
+    #[test]
+    fn keeps_to_the_limits() {
+        let heuristic = || Heuristic::Ordered.build();
+        let mut limited = generate::pigeonhole(5);
+        limited.set_conflict_limit(Some(10));
+        assert!(matches!(limited.solve_traced(heuristic().as_mut()), TraceResult::Unknown));
+        let mut interrupted = generate::pigeonhole(5);
+        interrupted.set_interrupt(Some(Arc::new(AtomicBool::new(true))));
+        assert!(matches!(interrupted.solve_traced(heuristic().as_mut()), TraceResult::Unknown));
+        let unlimited = generate::pigeonhole(4).solve_traced(heuristic().as_mut());
+        assert!(matches!(unlimited, TraceResult::Unsat(trace) if is_refutation(&trace)));
+    }
+}
@@ -0,0 +1,133 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use super::{Assignment, Formula, Literal};
+
+const UNVISITED: usize = usize::MAX;
+
+impl Formula {
+    // Whether every clause has at most two literals, which makes the formula
+    // solvable in linear time
+    pub(super) fn is_two_sat(&self) -> bool {
+        self.clauses.iter().all(|c| c.len() <= 2)
+    }
+
+    // Solves a 2-SAT formula using the strongly connected components of its
+    // implication graph, which has an edge !a -> b and !b -> a for each clause (a, b).
+    // The formula is unsatisfiable exactly when some literal and its negation
+    // end up in the same component
+    pub(super) fn solve_two_sat(&self) -> Option<Assignment> {
+        let mut graph = vec![vec![]; 2 * self.num_vars()];
+        for clause in self.clauses.iter() {
+            match *clause {
+                [] => return None,
+                [a] => graph[(!a).index()].push(a.index()),
+                [a, b] => {
+                    graph[(!a).index()].push(b.index());
+                    graph[(!b).index()].push(a.index());
+                }
+                _ => unreachable!("Clause with more than two literals"),
+            }
+        }
+
+        let component = strongly_connected_components(&graph);
+        let mut assignment = Assignment::new(self.num_vars());
+        for id in 0..self.num_vars() {
+            let pos = Literal::new(id, false);
+            let neg = !pos;
+            if component[pos.index()] == component[neg.index()] {
+                return None;
+            }
+            // Components are numbered in reverse topological order, so this picks
+            // the literal which can't imply its own negation
+            assignment.assign(if component[pos.index()] < component[neg.index()] { pos } else { neg });
+        }
+        Some(assignment)
+    }
+}
+
+// Tarjan's algorithm, done iteratively to handle large graphs. Returns the component
+// of each node, with components numbered in reverse topological order
+pub(super) fn strongly_connected_components(graph: &[Vec<usize>]) -> Vec<usize> {
+    let mut index = vec![UNVISITED; graph.len()];
+    let mut low = vec![0; graph.len()];
+    let mut on_stack = vec![false; graph.len()];
+    let mut component = vec![UNVISITED; graph.len()];
+    let mut stack = vec![];
+    let mut next_index = 0;
+    let mut num_components = 0;
+
+    for root in 0..graph.len() {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        index[root] = next_index;
+        low[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        // Each frame is a node along with the next of its edges to follow
+        let mut frames = vec![(root, 0)];
+        while let Some((v, edge)) = frames.pop() {
+            if let Some(&w) = graph[v].get(edge) {
+                frames.push((v, edge + 1));
+                if index[w] == UNVISITED {
+                    index[w] = next_index;
+                    low[w] = next_index;
+                    next_index += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    frames.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(index[w]);
+                }
+                continue;
+            }
+
+            if low[v] == index[v] {
+                loop {
+                    let w = stack.pop().unwrap();
+                    on_stack[w] = false;
+                    component[w] = num_components;
+                    if w == v {
+                        break;
+                    }
+                }
+                num_components += 1;
+            }
+            if let Some(&(parent, _)) = frames.last() {
+                low[parent] = low[parent].min(low[v]);
+            }
+        }
+    }
+    component
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::Rng;
+    use crate::solver::testing;
+
+    #[test]
+    fn solves_small_instances() {
+        let sat = testing::formula("p cnf 3 4\n1 2 0\n-1 3 0\n-2 -3 0\n-3 0\n");
+        let model = sat.solve_two_sat().expect("Satisfiable");
+        assert!(testing::is_model(&sat, &model));
+        // Each literal implies its negation through the others
+        let unsat = testing::formula("p cnf 2 4\n1 2 0\n-1 2 0\n1 -2 0\n-1 -2 0\n");
+        assert!(unsat.solve_two_sat().is_none());
+    }
+
+    #[test]
+    fn agrees_with_enumeration() {
+        let mut rng = Rng::new(3);
+        for _ in 0..300 {
+            let formula = testing::random_formula(&mut rng, 10, 12, 2);
+            assert!(formula.is_two_sat());
+            match formula.solve_two_sat() {
+                Some(model) => assert!(testing::is_model(&formula, &model)),
+                None => assert!(!testing::satisfiable(&formula)),
+            }
+        }
+    }
+}
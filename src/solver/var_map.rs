@@ -0,0 +1,104 @@
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::Read;
+use super::{Assignment, ClauseDb, Formula, Literal, ParseOptions};
+
+/// The original numbering of the variables of a formula read by
+/// `Formula::parse_dimacs_compact_str`, which only keeps the variables occurring in the
+/// clauses and numbers them consecutively in their original order
+#[derive(Clone, Debug)]
+pub struct VarMap {
+    // The original id of each variable, in increasing order
+    original: Vec<usize>,
+    // The number of variables of the input, including those which don't occur
+    num_original_vars: usize,
+}
+
+impl VarMap {
+    // Renames the variables of the clauses, which are over the given number of variables,
+    // to the consecutive ids of the map
+    fn compact(num_vars: usize, clauses: &mut ClauseDb) -> VarMap {
+        let mut original: Vec<_> = clauses.iter().flatten().map(|lit| lit.id()).collect();
+        original.sort_unstable();
+        original.dedup();
+        let map = VarMap { original, num_original_vars: num_vars };
+        // When every variable occurs, each keeps its id
+        if !map.is_identity() {
+            for i in 0..clauses.len() {
+                for lit in &mut clauses[i] {
+                    *lit = map.rename(*lit).expect("The variable occurs");
+                }
+            }
+        }
+        map
+    }
+
+    /// The number of variables left after compacting
+    pub fn num_vars(&self) -> usize {
+        self.original.len()
+    }
+
+    /// The number of variables of the input, including those which don't occur
+    pub fn num_original_vars(&self) -> usize {
+        self.num_original_vars
+    }
+
+    /// Whether every variable of the input occurs, so none was renamed
+    pub fn is_identity(&self) -> bool {
+        self.original.len() == self.num_original_vars
+    }
+
+    /// The literal of the compacted formula for the literal in the original numbering,
+    /// or None if its variable doesn't occur
+    pub fn rename(&self, lit: Literal) -> Option<Literal> {
+        let id = self.original.binary_search(&lit.id()).ok()?;
+        Some(Literal::new(id, lit.is_negated()))
+    }
+
+    /// The literal in the original numbering for the literal of the compacted formula
+    pub fn original(&self, lit: Literal) -> Literal {
+        Literal::new(self.original[lit.id()], lit.is_negated())
+    }
+
+    /// The model of the compacted formula in the original numbering, where the
+    /// variables which don't occur are unassigned, since they can take either value
+    pub fn restore(&self, model: &Assignment) -> Assignment {
+        let mut restored = Assignment::new(self.num_original_vars);
+        for (&id, &negated) in self.original.iter().zip(&model.0) {
+            restored.0[id] = negated;
+        }
+        restored
+    }
+}
+
+impl Formula {
+    /// Parses a DIMACS file with its variables compacted, see `parse_dimacs_compact_str`
+    #[cfg(feature = "std")]
+    pub fn parse_dimacs_compact(mut file: File, options: ParseOptions)
+        -> Result<(Formula, VarMap), String> {
+        let mut buf = vec![];
+        file.read_to_end(&mut buf).map_err(|_| "Error while reading file")?;
+        Formula::parse_dimacs_compact_bytes(&buf, options)
+    }
+
+    /// Parses DIMACS text like `parse_dimacs_str_with`, but leaves out the variables which
+    /// don't occur in any clause and numbers the rest consecutively, so inputs which
+    /// declare far more variables than they use, or use huge ids, don't blow up the
+    /// search. Returns the map back to the original numbering along with the formula
+    pub fn parse_dimacs_compact_str(input: &str, options: ParseOptions)
+        -> Result<(Formula, VarMap), String> {
+        Formula::parse_dimacs_compact_bytes(input.as_bytes(), options)
+    }
+
+    fn parse_dimacs_compact_bytes(input: &[u8], options: ParseOptions)
+        -> Result<(Formula, VarMap), String> {
+        let (num_vars, mut clauses, duplicates) = Formula::parse_dimacs_clauses(input, options)?;
+        let map = VarMap::compact(num_vars, &mut clauses);
+        Ok((Formula::from_clauses(map.num_vars(), clauses, duplicates), map))
+    }
+}
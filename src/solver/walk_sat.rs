@@ -0,0 +1,139 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::rng::Rng;
+use super::{interrupted, timed_out, Assignment, Clause, Formula, Literal, SolveResult};
+
+// Probability of flipping a random literal of the picked clause instead of a greedy one
+const NOISE: f64 = 0.567;
+
+impl Formula {
+    // WalkSAT local search: starting from a random assignment, repeatedly picks a
+    // falsified clause and flips one of its variables, preferring the one whose flip
+    // falsifies the fewest other clauses. Gives up after `max_flips` flips, or once
+    // the time limit runs out or the search is interrupted. No clause may be empty
+    pub(super) fn walk_sat(&self, max_flips: u64, seed: u64) -> SolveResult {
+        let mut rng = Rng::new(seed);
+        // Variables the hint doesn't assign start out random
+        let mut values: Vec<_> = (0..self.num_vars())
+            .map(|id| {
+                let random = rng.chance(0.5);
+                self.hinted(id).map_or(random, |negated| !negated)
+            })
+            .collect();
+        // The break counts assume each literal occurs once per clause, so duplicates are
+        // removed, and tautologies left out since no assignment falsifies them
+        let clauses: Vec<_> = self.clauses.iter()
+            .map(|c| c.normalized())
+            .filter(|c| !c.windows(2).any(|pair| pair[0] == !pair[1]))
+            .collect();
+        let mut occurrences = vec![vec![]; 2 * self.num_vars()];
+        for (i, clause) in clauses.iter().enumerate() {
+            for lit in clause {
+                occurrences[lit.index()].push(i);
+            }
+        }
+
+        // The number of true literals in each clause, and the falsified clauses
+        // along with their positions in that list
+        let mut true_count: Vec<_> = clauses.iter()
+            .map(|c| c.iter().filter(|l| values[l.id()] != l.is_negated()).count())
+            .collect();
+        let mut falsified = vec![];
+        let mut position = vec![0; clauses.len()];
+        for (i, &count) in true_count.iter().enumerate() {
+            if count == 0 {
+                position[i] = falsified.len();
+                falsified.push(i);
+            }
+        }
+
+        for flip in 0..max_flips {
+            let budget = &self.budget;
+            if flip % 1024 == 0 && (timed_out(budget.deadline) || interrupted(&budget.interrupt)) {
+                return SolveResult::Unknown;
+            }
+            if falsified.is_empty() {
+                let mut assignment = Assignment::new(self.num_vars());
+                for (id, &value) in values.iter().enumerate() {
+                    assignment.assign(Literal::new(id, !value));
+                }
+                return SolveResult::Sat(assignment);
+            }
+
+            // Flipping a literal of a falsified clause breaks the clauses in which
+            // its negation is the only true literal
+            let clause = &clauses[falsified[rng.below(falsified.len())]];
+            let breaks: Vec<_> = clause.iter()
+                .map(|&l| occurrences[(!l).index()].iter().filter(|&&j| true_count[j] == 1).count())
+                .collect();
+            let min_breaks = *breaks.iter().min().unwrap();
+            let lit = if min_breaks > 0 && rng.chance(NOISE) {
+                clause[rng.below(clause.len())]
+            } else {
+                let best: Vec<_> = (0..clause.len()).filter(|&i| breaks[i] == min_breaks).collect();
+                clause[best[rng.below(best.len())]]
+            };
+
+            values[lit.id()] = !lit.is_negated();
+            for &j in &occurrences[lit.index()] {
+                true_count[j] += 1;
+                if true_count[j] == 1 {
+                    let last = *falsified.last().unwrap();
+                    position[last] = position[j];
+                    falsified.swap_remove(position[j]);
+                }
+            }
+            for &j in &occurrences[(!lit).index()] {
+                true_count[j] -= 1;
+                if true_count[j] == 0 {
+                    position[j] = falsified.len();
+                    falsified.push(j);
+                }
+            }
+        }
+        SolveResult::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::heuristic::Heuristic;
+    use crate::rng::Rng;
+    use crate::solver::testing;
+    use crate::solver::{Engine, SolveResult};
+
+    // Solves the formula with WalkSAT from the given seed
+    fn walk_sat(input: &str, seed: u64) -> SolveResult {
+        let mut formula = testing::formula(input);
+        formula.set_engine(Engine::WalkSat { max_flips: 20_000, seed });
+        formula.solve(Heuristic::Ordered.build().as_mut())
+    }
+
+    #[test]
+    fn repeated_literals_are_counted_once() {
+        let input = "p cnf 3 3\n-1 -1 -1 0\n1 -3 0\n1 -3 3 -3 0\n";
+        let formula = testing::formula(input);
+        for seed in 0..16 {
+            match walk_sat(input, seed) {
+                SolveResult::Sat(model) => assert!(testing::is_model(&formula, &model)),
+                _ => panic!("No model found from seed {}", seed),
+            }
+        }
+    }
+
+    #[test]
+    fn models_satisfy_random_formulas() {
+        let mut rng = Rng::new(7);
+        for seed in 0..200 {
+            let formula = testing::random_formula(&mut rng, 6, 12, 4);
+            let satisfiable = testing::satisfiable(&formula);
+            let mut walk = formula.clone();
+            walk.set_engine(Engine::WalkSat { max_flips: 20_000, seed });
+            match walk.solve(Heuristic::Ordered.build().as_mut()) {
+                SolveResult::Sat(model) => assert!(testing::is_model(&formula, &model)),
+                SolveResult::Unsat => assert!(!satisfiable),
+                SolveResult::Unknown => assert!(!satisfiable, "Gave up on a satisfiable formula"),
+            }
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Gaussian elimination of XOR constraints during the search. The elimination is not
+//! incremental: each change to the assignment of a variable of the system reruns it on
+//! a copy of the whole matrix, rather than keeping an echelon form up to date across
+//! assignments and backtracks, so every change costs a full elimination
+
+use alloc::borrow::ToOwned;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::Read;
+use super::{Assignment, ExternalPropagator, Formula, Literal};
+
+// A XOR constraint over the columns of the system, as a bitset of its variables and the
+// parity they must sum to
+#[derive(Clone)]
+struct Row {
+    bits: Vec<u64>,
+    parity: bool,
+}
+
+impl Row {
+    fn get(&self, column: usize) -> bool {
+        self.bits[column / 64] >> (column % 64) & 1 == 1
+    }
+
+    fn add(&mut self, other: &Row) {
+        for (word, other) in self.bits.iter_mut().zip(&other.bits) {
+            *word ^= other;
+        }
+        self.parity ^= other.parity;
+    }
+
+    fn columns(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits.iter().enumerate().flat_map(|(i, &word)| {
+            (0..64).filter(move |bit| word >> bit & 1 == 1).map(move |bit| i * 64 + bit)
+        })
+    }
+}
+
+/// XOR constraints, reasoned about by Gaussian elimination during the search instead of
+/// being encoded, for `Formula::solve_with_propagator`. After the assignment changes,
+/// the system is eliminated over the unassigned variables, and every constraint it
+/// implies which is violated or has a single unassigned variable left is added as a
+/// clause over the variables it spans. The whole system is eliminated anew each time,
+/// so this suits a moderate number of constraints
+#[derive(Default)]
+pub struct XorConstraints {
+    rows: Vec<Row>,
+    // The variable of each column, and the column of each variable
+    vars: Vec<usize>,
+    columns: BTreeMap<usize, usize>,
+    // Whether a variable of the system has changed since the last elimination
+    dirty: bool,
+    // Clauses found by the last elimination which are yet to be added
+    pending: Vec<Vec<Literal>>,
+    // The clauses added so far, which the search keeps
+    added: BTreeSet<Vec<Literal>>,
+}
+
+impl XorConstraints {
+    /// Creates a propagator without constraints
+    pub fn new() -> Self {
+        XorConstraints::default()
+    }
+
+    /// Demands that an odd number of the literals are true. Negated literals flip the
+    /// parity, so `x1 ^ !x2` holds exactly if x1 and x2 are equal
+    pub fn add_xor(&mut self, literals: &[Literal]) {
+        let mut parity = true;
+        let mut vars = BTreeSet::new();
+        for lit in literals {
+            parity ^= lit.is_negated();
+            // Variables occurring twice cancel out
+            if !vars.insert(lit.id()) {
+                vars.remove(&lit.id());
+            }
+        }
+        for &var in &vars {
+            if !self.columns.contains_key(&var) {
+                self.columns.insert(var, self.vars.len());
+                self.vars.push(var);
+            }
+        }
+        let words = self.vars.len().div_ceil(64);
+        for row in &mut self.rows {
+            row.bits.resize(words, 0);
+        }
+        let mut row = Row { bits: vec![0; words], parity };
+        for var in vars {
+            let column = self.columns[&var];
+            row.bits[column / 64] |= 1 << (column % 64);
+        }
+        self.rows.push(row);
+        self.dirty = true;
+    }
+
+    /// The number of XOR constraints
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether there are no XOR constraints
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    // Eliminates the system after substituting the assignment, and returns the clauses
+    // for the eliminated constraints which are violated or unit. A constraint in which
+    // only assigned variables remain, or only one unassigned one, is implied by the
+    // values of its assigned variables, so its clause consists of their negations along
+    // with the literal it implies, if any
+    fn eliminate(&self, assignment: &Assignment) -> Vec<Vec<Literal>> {
+        let value = |column: usize| assignment.0[self.vars[column]].map(|negated| !negated);
+        let mut rows = self.rows.clone();
+        // The rows before this one have a pivot column, which no other row contains
+        let mut rank = 0;
+        for column in 0..self.vars.len() {
+            if value(column).is_some() {
+                continue;
+            }
+            let Some(pivot) = (rank..rows.len()).find(|&r| rows[r].get(column)) else {
+                continue;
+            };
+            rows.swap(rank, pivot);
+            let (above, below) = rows.split_at_mut(rank);
+            let (pivot_row, below) = below.split_first_mut().unwrap();
+            for row in above.iter_mut().chain(below) {
+                if row.get(column) {
+                    row.add(pivot_row);
+                }
+            }
+            rank += 1;
+        }
+
+        let mut clauses = vec![];
+        for row in &rows {
+            let mut clause = vec![];
+            let mut parity = row.parity;
+            let mut unassigned = vec![];
+            for column in row.columns() {
+                match value(column) {
+                    Some(value) => {
+                        parity ^= value;
+                        clause.push(Literal::new(self.vars[column], value));
+                    }
+                    None => unassigned.push(column),
+                }
+            }
+            match unassigned[..] {
+                [] if parity => (),
+                [column] => clause.push(Literal::new(self.vars[column], !parity)),
+                _ => continue,
+            }
+            clause.sort_unstable();
+            clauses.push(clause);
+        }
+        clauses
+    }
+}
+
+impl ExternalPropagator for XorConstraints {
+    fn notify_assignment(&mut self, lit: Literal) {
+        self.dirty |= self.columns.contains_key(&lit.id());
+    }
+
+    fn notify_backtrack(&mut self, lit: Literal) {
+        self.dirty |= self.columns.contains_key(&lit.id());
+    }
+
+    fn external_clause(&mut self, assignment: &Assignment) -> Option<Vec<Literal>> {
+        if self.dirty {
+            self.dirty = false;
+            self.pending = self.eliminate(assignment);
+        }
+        while let Some(clause) = self.pending.pop() {
+            if self.added.insert(clause.clone()) {
+                return Some(clause);
+            }
+        }
+        None
+    }
+
+    fn check_model(&mut self, model: &Assignment) -> Option<Vec<Literal>> {
+        self.eliminate(model).into_iter().find(|clause| self.added.insert(clause.clone()))
+    }
+}
+
+impl Formula {
+    /// Parses a DIMACS file with XOR clauses, see `parse_dimacs_xor_str`
+    #[cfg(feature = "std")]
+    pub fn parse_dimacs_xor(mut file: File) -> Result<(Formula, XorConstraints), String> {
+        let mut buf = String::new();
+        file.read_to_string(&mut buf).map_err(|_| "Error while reading file")?;
+        Formula::parse_dimacs_xor_str(&buf)
+    }
+
+    /// Parses DIMACS text in which lines starting with `x`, such as `x1 -2 3 0`, are XOR
+    /// clauses as in CryptoMiniSat, which demand that an odd number of their literals
+    /// are true. The clause count of the problem line includes the XOR clauses
+    pub fn parse_dimacs_xor_str(input: &str) -> Result<(Formula, XorConstraints), String> {
+        let num_xors = input.lines().filter(|l| l.starts_with('x')).count();
+        let mut cnf = String::new();
+        let mut xor_lines = vec![];
+        for line in input.lines() {
+            if let Some(xor) = line.strip_prefix('x') {
+                xor_lines.push(xor);
+                continue;
+            }
+            if line.starts_with('p') {
+                let mut params: Vec<_> = line.split_whitespace().map(str::to_owned).collect();
+                if let Some(count) = params.get_mut(3) {
+                    if let Ok(num_clauses) = count.parse::<usize>() {
+                        *count = num_clauses.checked_sub(num_xors)
+                            .ok_or("Too many clauses")?
+                            .to_string();
+                    }
+                }
+                cnf.push_str(&params.join(" "));
+            } else {
+                cnf.push_str(line);
+            }
+            cnf.push('\n');
+        }
+        let formula = Formula::parse_dimacs_str(&cnf)?;
+
+        let mut xors = XorConstraints::new();
+        for line in xor_lines {
+            let mut literals = vec![];
+            let mut tokens = line.split_whitespace();
+            loop {
+                let v = tokens.next().ok_or("Missing 0 at the end of a XOR clause")?;
+                let v: isize = v.parse().map_err(|_| format!("Illegal variable '{}'", v))?;
+                if v == 0 {
+                    break;
+                }
+                if v.unsigned_abs() > formula.num_vars() {
+                    return Err(format!("Variable {} is out of range", v.unsigned_abs()));
+                }
+                literals.push(Literal::from_var(v));
+            }
+            if tokens.next().is_some() {
+                return Err("Only one XOR clause is allowed per line".to_owned());
+            }
+            xors.add_xor(&literals);
+        }
+        Ok((formula, xors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use crate::heuristic::Heuristic;
+    use crate::rng::Rng;
+    use crate::solver::testing;
+    use crate::solver::{Formula, Literal, SolveResult};
+    use super::XorConstraints;
+
+    // Whether an odd number of the literals is true
+    fn odd(literals: &[Literal], values: &[bool]) -> bool {
+        literals.iter().filter(|l| values[l.id()] != l.is_negated()).count() % 2 == 1
+    }
+
+    // Solves the formula along with the XOR constraints over the literals
+    fn solve(formula: &Formula, xors: &[Vec<Literal>]) -> SolveResult {
+        let mut constraints = XorConstraints::new();
+        for xor in xors {
+            constraints.add_xor(xor);
+        }
+        let mut heuristic = Heuristic::Ordered.build();
+        formula.clone().solve_with_propagator(heuristic.as_mut(), &mut constraints)
+    }
+
+    #[test]
+    fn solves_small_instances() {
+        let input = "p cnf 3 4\nx1 2 0\nx2 3 0\nx-1 3 0\n1 3 0\n";
+        let (formula, mut xors) = Formula::parse_dimacs_xor_str(input).expect("Valid input");
+        assert_eq!(xors.len(), 3);
+        match formula.solve_with_propagator(Heuristic::Ordered.build().as_mut(), &mut xors) {
+            SolveResult::Sat(model) => assert_eq!(model.completed_values(), [true, false, true]),
+            _ => panic!("Satisfiable"),
+        }
+        // Adding up the three constraints gives 0 = 1
+        let input = "p cnf 3 3\nx1 2 0\nx2 3 0\nx1 3 0\n";
+        let (formula, mut xors) = Formula::parse_dimacs_xor_str(input).expect("Valid input");
+        let result = formula.solve_with_propagator(Heuristic::Ordered.build().as_mut(), &mut xors);
+        assert!(matches!(result, SolveResult::Unsat));
+    }
+
+    #[test]
+    fn agrees_with_enumeration() {
+        let mut rng = Rng::new(23);
+        let num_vars = 7;
+        for _ in 0..300 {
+            let formula = testing::random_formula(&mut rng, num_vars, 3, 3);
+            let xors: Vec<Vec<_>> = (0..1 + rng.below(4))
+                .map(|_| (0..1 + rng.below(4))
+                    .map(|_| Literal::new(rng.below(num_vars), rng.chance(0.5)))
+                    .collect())
+                .collect();
+            let satisfied = |values: &[bool]| {
+                testing::satisfied_by(&formula, values) && xors.iter().all(|x| odd(x, values))
+            };
+            let satisfiable = (0..1 << num_vars)
+                .any(|bits| satisfied(&testing::values(num_vars, bits)));
+            match solve(&formula, &xors) {
+                SolveResult::Sat(model) => assert!(satisfied(&model.completed_values())),
+                SolveResult::Unsat => assert!(!satisfiable),
+                SolveResult::Unknown => panic!("Gave up without limits"),
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+// A wasm-bindgen wrapper for using the solver from JavaScript. Build it with
+// `cargo rustc --release --lib --target wasm32-unknown-unknown --features wasm
+// --crate-type cdylib` and run wasm-bindgen on the result
+
+use wasm_bindgen::prelude::*;
+use crate::config::SolverConfig;
+use crate::solver::{Formula, SolveResult, Solver};
+
+/// Solves a formula given as DIMACS text. Returns one DIMACS literal per variable,
+/// in order, which is 0 for variables the model leaves unassigned, or `null` if the
+/// formula is unsatisfiable. Throws if the text is not valid DIMACS
+#[wasm_bindgen]
+pub fn solve(dimacs: &str) -> Result<Option<Vec<i32>>, JsError> {
+    let formula = Formula::parse_dimacs_str(dimacs).map_err(|e| JsError::new(&e))?;
+    match Solver::with_config(formula, SolverConfig::default()).solve() {
+        SolveResult::Sat(model) => Ok(Some(model.dimacs_literals())),
+        SolveResult::Unsat => Ok(None),
+        SolveResult::Unknown => Err(JsError::new("The search gave up")),
+    }
+}